@@ -0,0 +1,39 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::fs;
+use wordfun::{Anagram, FindWord, Lexicon};
+
+/// Compares single-threaded and parallel searches over the full lexicon. Run from the `api`
+/// directory with `cargo bench`.
+fn search(c: &mut Criterion) {
+    let text = fs::read_to_string("data/lexicon.txt").expect("Could not read data/lexicon.txt");
+    let lex = Lexicon::new(text.lines());
+    // One thread per core, as with --search-threads 0.
+    let pool = rayon::ThreadPoolBuilder::new().build().unwrap();
+
+    let mut group = c.benchmark_group("anagram");
+    for query in &["spear", "pioneering tsar", "r.t.in..."] {
+        let pattern = Anagram::parse(query).unwrap();
+        group.bench_with_input(BenchmarkId::new("serial", query), &pattern, |b, p| {
+            b.iter(|| lex.search(black_box(p.clone())).count())
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", query), &pattern, |b, p| {
+            b.iter(|| lex.par_search(black_box(p.clone()), &pool).count())
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("find_word");
+    for query in &["h.r./...l../e.g", "........", "a........."] {
+        let pattern = FindWord::parse(query).unwrap();
+        group.bench_with_input(BenchmarkId::new("serial", query), &pattern, |b, p| {
+            b.iter(|| lex.search(black_box(p.clone())).count())
+        });
+        group.bench_with_input(BenchmarkId::new("parallel", query), &pattern, |b, p| {
+            b.iter(|| lex.par_search(black_box(p.clone()), &pool).count())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, search);
+criterion_main!(benches);
@@ -1,40 +1,77 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::convert::TryFrom;
 use std::convert::TryInto;
 
 /// A string containing only lowercase ASCII letters.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Default, Clone)]
-pub struct AsciiString(Vec<u8>);
+///
+/// This normally owns its letters, but an `AsciiString<&[u8]>` borrows them, as when an
+/// [`Entry`](crate::Entry) in a [`Lexicon`](crate::Lexicon) shows the letters stored there.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Default, Clone, Copy)]
+pub struct AsciiString<B = Vec<u8>>(B);
 
-impl AsciiString {
+impl<'a> AsciiString<&'a [u8]> {
+    /// Borrows letters that are already known to be lowercase ASCII.
+    pub(crate) fn borrowed(letters: &'a [u8]) -> Self {
+        Self(letters)
+    }
+
+    /// Copies the letters into an owned `AsciiString`.
+    pub fn to_owned(&self) -> AsciiString {
+        AsciiString(self.0.to_vec())
+    }
+}
+
+impl<B: AsRef<[u8]>> AsciiString<B> {
     /// Returns the number of characters in the string.
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.0.as_ref().len()
     }
 
     /// Returns `true` if the string is empty.
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.0.as_ref().is_empty()
     }
 
     /// Converts the ASCII string to a string slice.
     pub fn to_str(&self) -> &str {
         // OK to unwrap this because the bytes are all ASCII.
-        std::str::from_utf8(&self.0).unwrap()
+        std::str::from_utf8(self.0.as_ref()).unwrap()
     }
 }
 
-impl std::fmt::Debug for AsciiString {
+impl<B: AsRef<[u8]>> std::fmt::Debug for AsciiString<B> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "AsciiString({:?})", self.to_str())
     }
 }
 
-impl AsRef<[u8]> for AsciiString {
+impl<B: AsRef<[u8]>> AsRef<[u8]> for AsciiString<B> {
     fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+/// Lets a set of `AsciiString`s be searched with borrowed letters.
+impl std::borrow::Borrow<[u8]> for AsciiString {
+    fn borrow(&self) -> &[u8] {
         &self.0
     }
 }
 
+impl Serialize for AsciiString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+/// Checks the letters, so that a corrupt file can't make an `AsciiString` that isn't ASCII.
+impl<'de> Deserialize<'de> for AsciiString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        Self::try_from(bytes).map_err(|_| de::Error::custom("expected ASCII letters"))
+    }
+}
+
 impl From<AsciiString> for Vec<u8> {
     fn from(val: AsciiString) -> Self {
         val.0
@@ -1,9 +1,56 @@
-use crate::wordnet::{from_wordnet, stem, DefinitionList, DictionaryData, WordType};
-use deunicode::deunicode;
+mod source;
 
-#[derive(Default)]
+use crate::phrase_key;
+use crate::wordnet::{stem_phrase, WordType, Wordnet};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub use source::{DictionarySource, Glossary, SourceEntry, SourceError};
+
+/// The name given to definitions added with [`Dictionary::insert`].
+const INSERTED: &str = "custom";
+
+#[derive(Default, Serialize, Deserialize)]
 pub struct Dictionary {
-    entries: DictionaryData,
+    /// Definitions, keyed by [`phrase_key`] so that spelling variants of a phrase find the same
+    /// entry.
+    entries: HashMap<String, Vec<Sense>>,
+    /// The names of the sources that definitions came from, indexed by `Sense::source`.
+    sources: Vec<String>,
+}
+
+/// A definition as stored in the dictionary.
+#[derive(Serialize, Deserialize)]
+struct Sense {
+    /// The headword as the source spelled it, with spaces in place of Wordnet's underscores.
+    headword: String,
+    word_type: Option<WordType>,
+    text: String,
+    source: usize,
+}
+
+/// A definition found in a [`Dictionary`], along with where it came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Definition<'a> {
+    /// The headword that was defined, which may be spelled differently from the search term.
+    pub headword: &'a str,
+    pub text: &'a str,
+    /// The part of speech, if the source says.
+    pub word_type: Option<WordType>,
+    /// The name of the [`DictionarySource`] the definition came from.
+    pub source: &'a str,
+}
+
+/// Where a source's definitions go relative to the ones already in the dictionary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precedence {
+    /// The source's definitions are preferred to existing ones for the same headword.
+    Before,
+    /// The source's definitions are only used if there's no existing one for the headword (or
+    /// for the part of speech being looked up).
+    After,
+    /// The source's definitions replace any existing ones for the same headword.
+    Replace,
 }
 
 impl Dictionary {
@@ -11,10 +58,10 @@ impl Dictionary {
         Self::default()
     }
 
-    pub fn from_wordnet() -> Self {
-        Self {
-            entries: from_wordnet(),
-        }
+    pub fn from_wordnet(wordnet: &Wordnet) -> Self {
+        let mut dict = Self::new();
+        dict.add_source(wordnet, Precedence::After);
+        dict
     }
 
     pub fn insert<S: Into<String>, T: Into<String>>(
@@ -23,11 +70,56 @@ impl Dictionary {
         defn: T,
         word_type: WordType,
     ) {
-        let lemma = lemma.into();
-        if let Some(entry) = self.entries.get_mut(&lemma) {
-            entry.push((word_type, defn.into()));
-        } else {
-            self.entries.insert(lemma, vec![(word_type, defn.into())]);
+        let source = self.source_id(INSERTED);
+        let headword = lemma.into().replace('_', " ");
+        self.entries
+            .entry(phrase_key(&headword))
+            .or_default()
+            .push(Sense {
+                headword,
+                word_type: Some(word_type),
+                text: defn.into(),
+                source,
+            });
+    }
+
+    /// Adds every definition from a source. Headwords are folded the same way as search terms,
+    /// so a glossary can say "Jack o' lantern" where Wordnet says "jack-o'-lantern".
+    pub fn add_source(&mut self, source: &dyn DictionarySource, precedence: Precedence) {
+        let source_id = self.source_id(source.name());
+        let mut new_entries: HashMap<String, Vec<Sense>> = HashMap::new();
+        for entry in source.entries() {
+            let headword = entry.headword.replace('_', " ");
+            new_entries
+                .entry(phrase_key(&headword))
+                .or_default()
+                .push(Sense {
+                    headword,
+                    word_type: entry.word_type,
+                    text: entry.definition,
+                    source: source_id,
+                });
+        }
+
+        for (key, senses) in new_entries {
+            let existing = self.entries.entry(key).or_default();
+            match precedence {
+                Precedence::Before => {
+                    existing.splice(0..0, senses);
+                }
+                Precedence::After => existing.extend(senses),
+                Precedence::Replace => *existing = senses,
+            }
+        }
+    }
+
+    fn source_id(&mut self, name: &str) -> usize {
+        match self.sources.iter().position(|source| source == name) {
+            Some(id) => id,
+            None => {
+                self.sources.push(name.to_string());
+                self.sources.len() - 1
+            }
         }
     }
 
@@ -41,48 +133,92 @@ impl Dictionary {
         self.len() == 0
     }
 
+    /// Returns the parts of speech the dictionary has definitions for, in order of preference.
+    pub fn word_types(&self, lemma: &str) -> Vec<WordType> {
+        let mut word_types = Vec::new();
+        for sense in self.entries.get(&phrase_key(lemma)).into_iter().flatten() {
+            if let Some(word_type) = sense.word_type {
+                if !word_types.contains(&word_type) {
+                    word_types.push(word_type);
+                }
+            }
+        }
+        word_types
+    }
+
+    /// Returns the parts of speech a term could be, including those of its base forms. For
+    /// example, "dogs" could be a plural noun or a verb in the third person.
+    pub fn guess_word_types(&self, term: &str) -> Vec<WordType> {
+        let mut word_types = self.word_types(term);
+        for stemmed in stem_phrase(&phrase_key(term)) {
+            let known = self
+                .entries
+                .get(&stemmed.base_form)
+                .and_then(|senses| senses.for_type(stemmed.word_type))
+                .is_some();
+            if known && !word_types.contains(&stemmed.word_type) {
+                word_types.push(stemmed.word_type);
+            }
+        }
+        word_types
+    }
+
+    /// Returns an iterator over every definition in the dictionary.
+    pub fn definitions(&self) -> impl Iterator<Item = Definition<'_>> {
+        self.entries
+            .values()
+            .flatten()
+            .map(move |sense| self.definition(sense))
+    }
+
     /// Returns a definition of the given term, or `None` if no definition was found.
     ///
-    /// If the term contains accented characters, they are converted to a rough ASCII
-    /// equivalent (see [`deunicode`]). If a definition is not found, the search
-    /// continues with variants suggested by Wordnet's stemmer.
-    ///
-    /// [`deunicode`]: deunicode::deunicode
+    /// The term is folded with [`phrase_key`], so accents, capitals, apostrophes and the
+    /// difference between spaces and hyphens don't matter. If a definition is not found, the
+    /// search continues with variants suggested by Wordnet's stemmer; for a phrase, that means
+    /// variants of its head word ("alarm clocks" finds "alarm clock").
     pub fn lookup<'a>(&'a self, term: &str) -> Option<&'a str> {
-        let search_term = deunicode(term).to_lowercase().replace(" ", "_");
+        self.lookup_definition(term).map(|defn| defn.text)
+    }
+
+    /// Like [`lookup`](Self::lookup), but also says which source the definition came from.
+    pub fn lookup_definition(&self, term: &str) -> Option<Definition<'_>> {
+        let search_term = phrase_key(term);
         self.entries
             .get(&search_term)
-            .and_then(Definitions::first)
-            .map(AsRef::as_ref)
+            .and_then(|senses| senses.first())
             .or_else(|| self.lookup_stemming(&search_term))
+            .map(|sense| self.definition(sense))
     }
 
-    fn lookup_stemming(&self, term: &str) -> Option<&str> {
-        stem(term)
+    fn lookup_stemming(&self, term: &str) -> Option<&Sense> {
+        stem_phrase(term)
+            .into_iter()
             .filter_map(|stemmed| {
                 self.entries
                     .get(&stemmed.base_form)
-                    .and_then(|defs| defs.for_type(stemmed.word_type))
+                    .and_then(|senses| senses.for_type(stemmed.word_type))
             })
-            .map(AsRef::as_ref)
             .next()
     }
-}
 
-trait Definitions {
-    fn first(&self) -> Option<&str>;
-    fn for_type(&self, word_type: WordType) -> Option<&str>;
+    fn definition<'a>(&'a self, sense: &'a Sense) -> Definition<'a> {
+        Definition {
+            headword: &sense.headword,
+            text: &sense.text,
+            word_type: sense.word_type,
+            source: &self.sources[sense.source],
+        }
+    }
 }
 
-impl Definitions for DefinitionList {
-    fn first(&self) -> Option<&str> {
-        self.get(0).map(|(_, defn)| defn.as_ref())
-    }
+trait Senses {
+    fn for_type(&self, word_type: WordType) -> Option<&Sense>;
+}
 
-    fn for_type(&self, word_type: WordType) -> Option<&str> {
-        self.iter()
-            .find(|(t, _)| *t == word_type)
-            .map(|(_, defn)| defn.as_ref())
+impl Senses for [Sense] {
+    fn for_type(&self, word_type: WordType) -> Option<&Sense> {
+        self.iter().find(|sense| sense.word_type == Some(word_type))
     }
 }
 
@@ -114,6 +250,90 @@ mod test {
         assert_eq!(Some("Pumpkin carving"), dict.lookup("jack-o'-lantern"));
     }
 
+    #[test]
+    pub fn guess_word_types_with_stemming() {
+        let mut dict = Dictionary::new();
+        dict.insert("dog", "nice animal", WordType::Noun);
+        dict.insert("dog", "go after with the intent to catch", WordType::Verb);
+        dict.insert("slide", "a small flat piece of glass", WordType::Noun);
+        dict.insert("slide", "move smoothly along a surface", WordType::Verb);
+
+        assert_eq!(
+            dict.guess_word_types("dog"),
+            vec![WordType::Noun, WordType::Verb]
+        );
+        assert_eq!(
+            dict.guess_word_types("dogs"),
+            vec![WordType::Noun, WordType::Verb]
+        );
+        assert_eq!(dict.guess_word_types("slid"), vec![WordType::Verb]);
+        assert_eq!(dict.guess_word_types("hamster"), vec![]);
+    }
+
+    #[test]
+    pub fn layer_sources() {
+        let glossary = Glossary::from_tsv(
+            "crosswordese",
+            "erne\tsea eagle\nDog\tnoun\tcanine, in crosswords\n",
+        )
+        .unwrap();
+        let mut dict = Dictionary::new();
+        dict.insert("dog", "nice animal", WordType::Noun);
+        dict.add_source(&glossary, Precedence::After);
+
+        assert_eq!(dict.lookup("dog"), Some("nice animal"));
+        assert_eq!(
+            dict.lookup_definition("ernes"),
+            None,
+            "definitions without a part of speech can't be found by stemming"
+        );
+        assert_eq!(
+            dict.lookup_definition("erne"),
+            Some(Definition {
+                headword: "erne",
+                text: "sea eagle",
+                word_type: None,
+                source: "crosswordese"
+            })
+        );
+
+        dict.add_source(&glossary, Precedence::Before);
+        assert_eq!(dict.lookup("dog"), Some("canine, in crosswords"));
+        assert_eq!(dict.lookup("dogs"), Some("canine, in crosswords"));
+        assert_eq!(dict.word_types("dog"), vec![WordType::Noun]);
+
+        dict.add_source(&glossary, Precedence::Replace);
+        let sources: Vec<_> = dict
+            .definitions()
+            .filter(|defn| defn.headword == "Dog")
+            .map(|defn| defn.source)
+            .collect();
+        assert_eq!(sources, vec!["crosswordese"]);
+    }
+
+    #[test]
+    pub fn lookup_phrase_variants() {
+        let mut dict = Dictionary::new();
+        dict.insert(
+            "bee's_knees",
+            "something outstandingly good",
+            WordType::Noun,
+        );
+        dict.insert("alarm_clock", "a clock that wakes you", WordType::Noun);
+        dict.insert("run_away", "flee", WordType::Verb);
+
+        let defn = dict.lookup_definition("bees-knees").unwrap();
+        assert_eq!(defn.headword, "bee's knees");
+        assert_eq!(defn.text, "something outstandingly good");
+        assert_eq!(
+            dict.lookup("Bees' Knees"),
+            Some("something outstandingly good")
+        );
+        assert_eq!(dict.lookup("alarm-clocks"), Some("a clock that wakes you"));
+        assert_eq!(dict.lookup("running away"), Some("flee"));
+        assert_eq!(dict.lookup("alarm clockses"), None);
+    }
+
     #[test]
     pub fn lookup_accented() {
         let defn = "without the intrusion of a third person";
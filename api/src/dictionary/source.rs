@@ -0,0 +1,376 @@
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::wordnet::{WordType, Wordnet};
+
+/// A single definition from a [`DictionarySource`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceEntry {
+    pub headword: String,
+    /// The part of speech, if the source says.
+    pub word_type: Option<WordType>,
+    pub definition: String,
+}
+
+/// Somewhere definitions come from. Sources are layered into a [`Dictionary`] with
+/// [`Dictionary::add_source`].
+///
+/// [`Dictionary`]: super::Dictionary
+/// [`Dictionary::add_source`]: super::Dictionary::add_source
+pub trait DictionarySource {
+    /// A short name for the source, such as "wordnet", which is reported alongside each of its
+    /// definitions.
+    fn name(&self) -> &str;
+
+    /// Returns every definition in the source. Definitions of the same headword should come in
+    /// order of preference.
+    fn entries(&self) -> Box<dyn Iterator<Item = SourceEntry> + '_>;
+}
+
+/// Wordnet's definitions. Headwords are Wordnet lemmas, which use underscores between words,
+/// such as "alarm_clock".
+impl DictionarySource for Wordnet {
+    fn name(&self) -> &str {
+        "wordnet"
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = SourceEntry> + '_> {
+        Box::new(self.definitions().into_iter().flat_map(|(lemma, defs)| {
+            defs.into_iter()
+                .map(move |(word_type, definition)| SourceEntry {
+                    headword: lemma.clone(),
+                    word_type: Some(word_type),
+                    definition,
+                })
+        }))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SourceError {
+    #[error("Could not read {path:?}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Line {line}: {message}")]
+    Parse { line: usize, message: String },
+    #[error("Don't know how to read {0:?}: expected a .tsv, .jsonl, or dictd .index file")]
+    UnknownFormat(PathBuf),
+}
+
+/// A dictionary loaded from a local file, such as an in-house glossary.
+///
+/// Three formats are supported:
+///
+/// * TSV, with one definition per line as `headword<TAB>definition` or
+///   `headword<TAB>part of speech<TAB>definition`. Blank lines and lines starting with `#`
+///   are ignored.
+/// * JSON Lines in the format of a [Wiktextract] dump of Wiktionary, with one headword per line:
+///   `{"word": "erne", "pos": "noun", "senses": [{"glosses": ["A sea eagle."]}]}`
+/// * Uncompressed [dictd] files: a `.index` file and the `.dict` file next to it.
+///
+/// [Wiktextract]: https://github.com/tatuylonen/wiktextract
+/// [dictd]: https://linux.die.net/man/8/dictd
+#[derive(Debug)]
+pub struct Glossary {
+    name: String,
+    entries: Vec<SourceEntry>,
+}
+
+#[derive(Deserialize)]
+struct WiktionaryEntry {
+    word: String,
+    pos: Option<String>,
+    #[serde(default)]
+    senses: Vec<WiktionarySense>,
+}
+
+#[derive(Deserialize)]
+struct WiktionarySense {
+    #[serde(default)]
+    glosses: Vec<String>,
+}
+
+impl Glossary {
+    /// Reads a glossary, choosing the format from the file extension. The glossary is named
+    /// after the file, so "data/crosswordese.tsv" is called "crosswordese".
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SourceError> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("tsv") | Some("txt") => Self::from_tsv(name, &read_to_string(path)?),
+            Some("jsonl") => Self::from_jsonl(name, &read_to_string(path)?),
+            Some("index") => {
+                let data_path = path.with_extension("dict");
+                let data = fs::read(&data_path).map_err(|source| SourceError::Io {
+                    path: data_path,
+                    source,
+                })?;
+                Self::from_dictd(name, &read_to_string(path)?, &data)
+            }
+            _ => Err(SourceError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+
+    /// Parses tab-separated definitions. Lines starting with `#` are comments.
+    pub fn from_tsv<S: Into<String>>(name: S, text: &str) -> Result<Self, SourceError> {
+        let mut entries = Vec::new();
+        for (line_no, line) in numbered_lines(text).filter(|(_, line)| !line.starts_with('#')) {
+            let fields: Vec<&str> = line.splitn(3, '\t').map(str::trim).collect();
+            let (headword, word_type, definition) = match fields[..] {
+                [headword, definition] => (headword, None, definition),
+                [headword, pos, definition] => {
+                    let word_type: WordType = pos.parse().map_err(|err| SourceError::Parse {
+                        line: line_no,
+                        message: format!("{}", err),
+                    })?;
+                    (headword, Some(word_type), definition)
+                }
+                _ => {
+                    return Err(SourceError::Parse {
+                        line: line_no,
+                        message: "Expected a headword and a definition".to_string(),
+                    })
+                }
+            };
+            entries.push(SourceEntry {
+                headword: headword.to_string(),
+                word_type,
+                definition: definition.to_string(),
+            });
+        }
+        Ok(Self {
+            name: name.into(),
+            entries,
+        })
+    }
+
+    /// Parses a Wiktextract JSON Lines dump. Parts of speech that don't map onto ours, such as
+    /// "name" or "phrase", are left unspecified.
+    pub fn from_jsonl<S: Into<String>>(name: S, text: &str) -> Result<Self, SourceError> {
+        let mut entries = Vec::new();
+        for (line_no, line) in numbered_lines(text) {
+            let entry: WiktionaryEntry =
+                serde_json::from_str(line).map_err(|err| SourceError::Parse {
+                    line: line_no,
+                    message: err.to_string(),
+                })?;
+            let word_type = entry.pos.and_then(|pos| pos.parse().ok());
+            // Subsenses repeat their parent's gloss first, so the last one is the most specific.
+            for gloss in entry.senses.iter().filter_map(|sense| sense.glosses.last()) {
+                entries.push(SourceEntry {
+                    headword: entry.word.clone(),
+                    word_type,
+                    definition: gloss.clone(),
+                });
+            }
+        }
+        Ok(Self {
+            name: name.into(),
+            entries,
+        })
+    }
+
+    /// Parses a dictd index and the contents of the matching `.dict` file. dictd doesn't record
+    /// parts of speech, so they are left unspecified.
+    pub fn from_dictd<S: Into<String>>(
+        name: S,
+        index: &str,
+        data: &[u8],
+    ) -> Result<Self, SourceError> {
+        let mut entries = Vec::new();
+        for (line_no, line) in numbered_lines(index) {
+            let parse_error = |message: &str| SourceError::Parse {
+                line: line_no,
+                message: message.to_string(),
+            };
+            let mut fields = line.split('\t');
+            let headword = fields.next().unwrap_or_default();
+            let offset = fields.next().and_then(decode_dictd_number);
+            let length = fields.next().and_then(decode_dictd_number);
+            let (offset, length) = match (offset, length) {
+                (Some(offset), Some(length)) => (offset, length),
+                _ => return Err(parse_error("Expected a headword, offset, and length")),
+            };
+            // Entries such as "00-database-info" describe the dictionary itself.
+            if headword.starts_with("00-database") || headword.starts_with("00database") {
+                continue;
+            }
+            let body = offset
+                .checked_add(length)
+                .and_then(|end| data.get(offset..end))
+                .ok_or_else(|| parse_error("Entry is past the end of the .dict file"))?;
+            let body = String::from_utf8_lossy(body);
+            // Most dictd entries start by repeating the headword on a line of its own.
+            let mut lines = body.lines().peekable();
+            if lines
+                .peek()
+                .map_or(false, |first| first.trim().eq_ignore_ascii_case(headword))
+            {
+                lines.next();
+            }
+            let definition = lines.flat_map(str::split_whitespace).collect::<Vec<_>>();
+            if !definition.is_empty() {
+                entries.push(SourceEntry {
+                    headword: headword.to_string(),
+                    word_type: None,
+                    definition: definition.join(" "),
+                });
+            }
+        }
+        Ok(Self {
+            name: name.into(),
+            entries,
+        })
+    }
+}
+
+impl DictionarySource for Glossary {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn entries(&self) -> Box<dyn Iterator<Item = SourceEntry> + '_> {
+        Box::new(self.entries.iter().cloned())
+    }
+}
+
+fn read_to_string(path: &Path) -> Result<String, SourceError> {
+    fs::read_to_string(path).map_err(|source| SourceError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Returns the non-blank lines, numbered from 1.
+fn numbered_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty())
+}
+
+/// Decodes an offset or length from a dictd index, which are written in base 64, most
+/// significant digit first.
+fn decode_dictd_number(digits: &str) -> Option<usize> {
+    digits.bytes().try_fold(0usize, |n, byte| {
+        let digit = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        n.checked_mul(64)?.checked_add(usize::from(digit))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn entries(glossary: &Glossary) -> Vec<(String, Option<WordType>, String)> {
+        glossary
+            .entries()
+            .map(|e| (e.headword, e.word_type, e.definition))
+            .collect()
+    }
+
+    #[test]
+    pub fn parse_tsv() {
+        let text = "# Crossword-ese\n\
+                    erne\tsea eagle\n\
+                    \n\
+                    etui\tnoun\tsmall ornamental case\n";
+        let glossary = Glossary::from_tsv("crosswordese", text).unwrap();
+        assert_eq!(glossary.name(), "crosswordese");
+        assert_eq!(
+            entries(&glossary),
+            vec![
+                ("erne".to_string(), None, "sea eagle".to_string()),
+                (
+                    "etui".to_string(),
+                    Some(WordType::Noun),
+                    "small ornamental case".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn parse_tsv_errors() {
+        match Glossary::from_tsv("bad", "erne\tsea eagle\netui\n") {
+            Err(SourceError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+        assert!(Glossary::from_tsv("bad", "etui\tpronoun\tsmall case\n").is_err());
+    }
+
+    #[test]
+    pub fn parse_jsonl() {
+        let text = r#"{"word": "erne", "pos": "noun", "senses": [{"glosses": ["A sea eagle."]}]}
+{"word": "Oreo", "pos": "name", "senses": [{"glosses": ["A cookie.", "A sandwich cookie."]}, {}]}"#;
+        let glossary = Glossary::from_jsonl("wiktionary", text).unwrap();
+        assert_eq!(
+            entries(&glossary),
+            vec![
+                (
+                    "erne".to_string(),
+                    Some(WordType::Noun),
+                    "A sea eagle.".to_string()
+                ),
+                ("Oreo".to_string(), None, "A sandwich cookie.".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn parse_dictd() {
+        let data = b"erne\n   sea eagle\n   of Europe\nsmall ornamental case\n";
+        // "erne" is at offset 0, length 31 ("f" in base 64); "etui" at 31, length 22 ("W").
+        let index = "00-database-short\tA\tA\nerne\tA\tf\netui\tf\tW\n";
+        let glossary = Glossary::from_dictd("gcide", index, data).unwrap();
+        assert_eq!(
+            entries(&glossary),
+            vec![
+                ("erne".to_string(), None, "sea eagle of Europe".to_string()),
+                (
+                    "etui".to_string(),
+                    None,
+                    "small ornamental case".to_string()
+                ),
+            ]
+        );
+        assert!(Glossary::from_dictd("gcide", "erne\tA\tZZ\n", data).is_err());
+        // An offset this big would overflow when the length is added.
+        assert!(Glossary::from_dictd("gcide", "erne\tP//////////\tB\n", data).is_err());
+    }
+
+    #[test]
+    pub fn comments_only_in_tsv() {
+        let data = b"the symbol for a number";
+        let glossary = Glossary::from_dictd("symbols", "#\tA\tX\n", data).unwrap();
+        assert_eq!(
+            entries(&glossary),
+            vec![("#".to_string(), None, "the symbol for a number".to_string())]
+        );
+        assert!(Glossary::from_jsonl("wiktionary", "# not JSON\n").is_err());
+    }
+
+    #[test]
+    pub fn decode_base64_numbers() {
+        assert_eq!(decode_dictd_number("A"), Some(0));
+        assert_eq!(decode_dictd_number("/"), Some(63));
+        assert_eq!(decode_dictd_number("BA"), Some(64));
+        assert_eq!(decode_dictd_number("b!"), None);
+    }
+}
@@ -0,0 +1,153 @@
+/// How many times each letter from a to z appears in a word, packed four bits to a letter.
+///
+/// This makes anagram search go faster than comparing [`SortedAscii`]s: checking whether one
+/// word's letters are all in another takes a couple of subtractions on a `u128`, instead of a
+/// loop over the letters. Each count goes up to 15, so words with more of any one letter
+/// than that can't be represented, and have to fall back to [`SortedAscii::is_subset`].
+///
+/// [`SortedAscii`]: crate::SortedAscii
+/// [`SortedAscii::is_subset`]: crate::SortedAscii::is_subset
+///
+/// # Examples
+///
+/// ```
+/// use wordfun::LetterCounts;
+///
+/// let tsar = LetterCounts::from_letters(b"tsar").unwrap();
+/// let rats = LetterCounts::from_letters(b"stare").unwrap();
+/// assert!(tsar.is_subset(rats));
+/// assert!(!rats.is_subset(tsar));
+/// assert_eq!(LetterCounts::from_letters(&[b'z'; 16]), None);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct LetterCounts(u128);
+
+impl LetterCounts {
+    /// The most times a letter can appear.
+    pub const MAX_COUNT: u32 = 15;
+
+    const LANE_BITS: u32 = 4;
+
+    /// The low four bits of each byte, covering the 13 even-numbered letters, a, c, e, ..., y.
+    /// Shifting the counts right by four bits lines the odd-numbered letters up with it.
+    const EVEN_LANES: u128 = 0x000f_0f0f_0f0f_0f0f_0f0f_0f0f_0f0f;
+
+    /// The fifth bit of each of the same bytes.
+    const GUARDS: u128 = (Self::EVEN_LANES << 1) & !Self::EVEN_LANES;
+
+    /// Counts the letters in a string of lowercase ASCII letters. Returns `None` if there's
+    /// anything else in the string, or if any letter appears more than [`MAX_COUNT`] times.
+    ///
+    /// [`MAX_COUNT`]: Self::MAX_COUNT
+    pub fn from_letters(letters: &[u8]) -> Option<Self> {
+        let mut counts = 0u128;
+        for &b in letters {
+            if !b.is_ascii_lowercase() {
+                return None;
+            }
+            let shift = u32::from(b - b'a') * Self::LANE_BITS;
+            if (counts >> shift) & 0xf == u128::from(Self::MAX_COUNT) {
+                return None;
+            }
+            counts += 1 << shift;
+        }
+        Some(Self(counts))
+    }
+
+    /// Returns true if every letter appears at least as many times in `other` as in `self`.
+    pub fn is_subset(self, other: LetterCounts) -> bool {
+        Self::lanes_at_most(self.0 & Self::EVEN_LANES, other.0 & Self::EVEN_LANES)
+            && Self::lanes_at_most(
+                (self.0 >> Self::LANE_BITS) & Self::EVEN_LANES,
+                (other.0 >> Self::LANE_BITS) & Self::EVEN_LANES,
+            )
+    }
+
+    /// Packs optional counts into a plain `u128`, for storing many of them compactly. No real
+    /// counts have the top bits set, so all ones stands for `None`.
+    pub(crate) fn to_raw(counts: Option<Self>) -> u128 {
+        counts.map_or(u128::MAX, |counts| counts.0)
+    }
+
+    /// Unpacks counts stored by [`to_raw`](Self::to_raw).
+    pub(crate) fn from_raw(raw: u128) -> Option<Self> {
+        if raw == u128::MAX {
+            None
+        } else {
+            Some(Self(raw))
+        }
+    }
+
+    /// Compares counts that have a byte each. Setting the guard bit adds 16 to each of `b`'s
+    /// counts, so subtracting `a`'s never borrows from the next byte, and the guard bit
+    /// survives exactly where `a`'s count is at most `b`'s.
+    fn lanes_at_most(a: u128, b: u128) -> bool {
+        ((b | Self::GUARDS) - a) & Self::GUARDS == Self::GUARDS
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SortedAscii;
+    use proptest::prelude::*;
+
+    #[test]
+    pub fn count_every_letter() {
+        let alphabet: Vec<u8> = (b'a'..=b'z').collect();
+        let counts = LetterCounts::from_letters(&alphabet).unwrap();
+        for &letter in &alphabet {
+            let one = LetterCounts::from_letters(&[letter]).unwrap();
+            let two = LetterCounts::from_letters(&[letter, letter]).unwrap();
+            assert!(one.is_subset(counts));
+            assert!(!two.is_subset(counts));
+        }
+        assert!(LetterCounts::default().is_subset(counts));
+        assert_eq!(LetterCounts::from_letters(b"Abc"), None);
+    }
+
+    #[test]
+    pub fn max_counts() {
+        let fifteen = LetterCounts::from_letters(&[b'z'; 15]).unwrap();
+        let fourteen = LetterCounts::from_letters(&[b'z'; 14]).unwrap();
+        assert!(fourteen.is_subset(fifteen));
+        assert!(!fifteen.is_subset(fourteen));
+    }
+
+    fn too_many_of_a_letter(letters: &[u8]) -> bool {
+        (b'a'..=b'z').any(|letter| {
+            letters.iter().filter(|&&b| b == letter).count() > LetterCounts::MAX_COUNT as usize
+        })
+    }
+
+    proptest! {
+        // The small alphabet gives counts on both sides of `MAX_COUNT`.
+        #[test]
+        fn agrees_with_sorted_ascii(
+            a in prop_oneof!["[a-z]{0,40}", "[a-c]{0,40}"],
+            b in prop_oneof!["[a-z]{0,40}", "[a-c]{0,40}"],
+        ) {
+            let (a, b) = (a.as_bytes(), b.as_bytes());
+            let (x, y) = (LetterCounts::from_letters(a), LetterCounts::from_letters(b));
+            prop_assert_eq!(x.is_none(), too_many_of_a_letter(a));
+            prop_assert_eq!(y.is_none(), too_many_of_a_letter(b));
+            let counted = match (x, y) {
+                (Some(x), Some(y)) => x.is_subset(y),
+                _ => return Ok(()),
+            };
+            let sorted = SortedAscii::from_bytes(a).is_subset(&SortedAscii::from_bytes(b));
+            prop_assert_eq!(counted, sorted);
+        }
+
+        #[test]
+        fn word_contains_its_own_letters(word in "[a-z]{0,30}", extra in "[a-z]{0,5}") {
+            let longer = format!("{}{}", word, extra);
+            if let (Some(x), Some(y)) = (
+                LetterCounts::from_letters(word.as_bytes()),
+                LetterCounts::from_letters(longer.as_bytes()),
+            ) {
+                prop_assert!(x.is_subset(y));
+            }
+        }
+    }
+}
@@ -1,26 +1,57 @@
 mod entry;
+mod parse_error;
 mod parse_word;
 mod results;
 mod search;
 mod search_key;
+mod tags;
 
-use std::slice;
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::mem;
+use std::ops::Range;
 
 pub use entry::Entry;
-pub use parse_word::parse_word;
+pub use parse_error::{ParseError, MAX_QUERY_LENGTH};
+pub use parse_word::{parse_word, phrase_key};
 pub use results::Results;
-pub use search::{Anagram, FindWord, Matches};
+pub use search::{Anagram, FindWord, Matches, Tagged};
 pub use search_key::SearchKey;
+pub use tags::{Tag, Tags};
 
-use crate::{AsciiString, SortedAscii, WordBreaks};
+use crate::wordnet::{inflections, Inflected, WordType, Wordnet};
+use crate::{AsciiString, LetterCounts, SortedAscii, WordBreaks};
 
 /// The searchable list of words and phrases.
 ///
 /// The Lexicon holds a list of [entries], which are pre-processed to help searching.
 ///
+/// Rather than keeping each entry separately, with three small allocations apiece, the Lexicon
+/// keeps each part of every entry together: all the words in one string, all the letters in one
+/// buffer, and so on, with offsets saying where each entry's part starts. That takes about a
+/// quarter of the memory, and searching reads straight through the buffers.
+///
 /// [entries]: [`Entry`]
+#[derive(Serialize, Deserialize)]
 pub struct Lexicon {
-    entries: Vec<Entry>,
+    /// Every word, one after another
+    words: String,
+    /// Where each word starts in `words`, plus the end of the last one
+    word_offsets: Vec<u32>,
+    /// Every entry's letters, one after another
+    letters: Vec<u8>,
+    /// Every entry's letters in alphabetical order, at the same offsets as `letters`
+    sorted: Vec<u8>,
+    /// Where each entry's letters start in `letters` and `sorted`, plus the end of the last
+    letter_offsets: Vec<u32>,
+    /// Each entry's letter counts, as in [`LetterCounts::to_raw`]
+    counts: Vec<u128>,
+    breaks: Vec<WordBreaks>,
+    tags: Vec<Tags>,
+    /// The index of every entry, ordered by letters, for looking words up
+    by_letters: Vec<u32>,
 }
 
 impl Lexicon {
@@ -29,29 +60,239 @@ impl Lexicon {
     where
         I: Iterator<Item = &'a str>,
     {
-        Self {
-            entries: words.map(Entry::from).collect(),
+        let mut lexicon = Self {
+            words: String::new(),
+            word_offsets: vec![0],
+            letters: Vec::new(),
+            sorted: Vec::new(),
+            letter_offsets: vec![0],
+            counts: Vec::new(),
+            breaks: Vec::new(),
+            tags: Vec::new(),
+            by_letters: Vec::new(),
+        };
+        for entry in words.map(Entry::from) {
+            lexicon.push(&entry);
         }
+        let mut by_letters: Vec<u32> = (0..offset(lexicon.len())).collect();
+        by_letters.sort_unstable_by(|&a, &b| {
+            lexicon.letters(a as usize).cmp(lexicon.letters(b as usize))
+        });
+        lexicon.by_letters = by_letters;
+        lexicon.shrink_to_fit();
+        lexicon
+    }
+
+    fn push(&mut self, entry: &Entry) {
+        self.words.push_str(entry.word());
+        self.word_offsets.push(offset(self.words.len()));
+        self.letters.extend_from_slice(entry.letters().as_ref());
+        self.sorted.extend_from_slice(entry.sorted().as_ref());
+        self.letter_offsets.push(offset(self.letters.len()));
+        self.counts
+            .push(LetterCounts::to_raw(entry.letter_counts()));
+        self.breaks.push(*entry.breaks());
+        self.tags.push(entry.tags());
+    }
+
+    pub(crate) fn shrink_to_fit(&mut self) {
+        self.words.shrink_to_fit();
+        self.word_offsets.shrink_to_fit();
+        self.letters.shrink_to_fit();
+        self.sorted.shrink_to_fit();
+        self.letter_offsets.shrink_to_fit();
+        self.counts.shrink_to_fit();
+        self.breaks.shrink_to_fit();
+        self.tags.shrink_to_fit();
+        self.by_letters.shrink_to_fit();
+    }
+
+    /// Checks that the buffers agree with each other, as they always do unless a saved
+    /// Lexicon has been corrupted.
+    pub(crate) fn is_consistent(&self) -> bool {
+        let n = self.len();
+        let well_formed = |offsets: &[u32], len: usize| {
+            offsets.len() == n + 1
+                && offsets[0] == 0
+                && offsets.windows(2).all(|pair| pair[0] <= pair[1])
+                && offsets[n] as usize == len
+        };
+        well_formed(&self.word_offsets, self.words.len())
+            && well_formed(&self.letter_offsets, self.letters.len())
+            && self.sorted.len() == self.letters.len()
+            && self.counts.len() == n
+            && self.breaks.len() == n
+            && self
+                .word_offsets
+                .iter()
+                .all(|&offset| self.words.is_char_boundary(offset as usize))
+            && self.letters.iter().all(u8::is_ascii_lowercase)
+            && self.sorted.iter().all(u8::is_ascii_lowercase)
+            && self.by_letters.len() == n
+            && self.by_letters.iter().all(|&i| (i as usize) < n)
+            && self
+                .by_letters
+                .windows(2)
+                .all(|pair| self.letters(pair[0] as usize) <= self.letters(pair[1] as usize))
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// How many bytes the Lexicon has allocated to hold its entries.
+    pub fn heap_size(&self) -> usize {
+        self.words.capacity()
+            + mem::size_of::<u32>() * self.word_offsets.capacity()
+            + self.letters.capacity()
+            + self.sorted.capacity()
+            + mem::size_of::<u32>() * self.letter_offsets.capacity()
+            + mem::size_of::<u128>() * self.counts.capacity()
+            + mem::size_of::<WordBreaks>() * self.breaks.capacity()
+            + mem::size_of::<Tags>() * self.tags.capacity()
+            + mem::size_of::<u32>() * self.by_letters.capacity()
     }
 
     /// Returns an iterator over all entries.
-    /// The iterator element type is `&Entry`.
-    pub fn entries(&self) -> Entries {
+    /// The iterator element type is `Entry<'_>`, a view onto the Lexicon's storage.
+    pub fn entries(&self) -> Entries<'_> {
         Entries {
-            it: self.entries.iter(),
+            lexicon: self,
+            indexes: 0..self.len(),
         }
     }
 
+    /// The entry at index `i`.
+    fn entry(&self, i: usize) -> Entry<'_> {
+        let letters = span(&self.letter_offsets, i);
+        Entry::from_parts(
+            self.word(i),
+            &self.letters[letters.clone()],
+            &self.sorted[letters],
+            LetterCounts::from_raw(self.counts[i]),
+            self.breaks[i],
+            self.tags[i],
+        )
+    }
+
+    /// The word at index `i`. Unlike `entry(i).word()`, this borrows from the Lexicon, so it
+    /// can outlive the entry.
+    fn word(&self, i: usize) -> &str {
+        &self.words[span(&self.word_offsets, i)]
+    }
+
+    /// The letters of entry `i`.
+    fn letters(&self, i: usize) -> &[u8] {
+        &self.letters[span(&self.letter_offsets, i)]
+    }
+
+    /// Whether any entry has the same letters as the word, so "Its" finds "it's".
+    pub fn contains(&self, word: &str) -> bool {
+        !self.find_letters(parse_word(word).0.as_ref()).is_empty()
+    }
+
+    /// The tags of every entry with the same letters as the word, so "new york" has the tags
+    /// of "New York".
+    pub fn word_tags(&self, word: &str) -> Tags {
+        self.find_letters(parse_word(word).0.as_ref())
+            .iter()
+            .fold(Tags::default(), |tags, &i| {
+                tags.union(self.tags[i as usize])
+            })
+    }
+
+    /// The indexes of the entries with these letters, as a run of `by_letters`.
+    fn find_letters(&self, letters: &[u8]) -> &[u32] {
+        let by_letters = &self.by_letters;
+        let found = match by_letters.binary_search_by(|&i| self.letters(i as usize).cmp(letters)) {
+            Ok(found) => found,
+            Err(_) => return &[],
+        };
+        let other = |&i: &u32| self.letters(i as usize) != letters;
+        let start = by_letters[..found]
+            .iter()
+            .rposition(other)
+            .map_or(0, |i| i + 1);
+        let end = by_letters[found..]
+            .iter()
+            .position(other)
+            .map_or(by_letters.len(), |i| found + i);
+        &by_letters[start..end]
+    }
+
+    /// Adds tags to entries. Words are matched by [`phrase_key`], so the tags for "aachen"
+    /// apply to "Aachen" as well.
+    pub fn tag<S, I>(&mut self, tags: I)
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = (S, Tag)>,
+    {
+        let mut by_key: HashMap<String, Tags> = HashMap::new();
+        for (word, tag) in tags {
+            by_key
+                .entry(phrase_key(word.as_ref()))
+                .or_default()
+                .insert(tag);
+        }
+        for i in 0..self.len() {
+            if let Some(&tags) = by_key.get(&phrase_key(self.word(i))) {
+                self.tags[i] = self.tags[i].union(tags);
+            }
+        }
+    }
+
+    /// Tags entries with the categories of their Wordnet senses, such as `Location` or `Animal`.
+    pub fn tag_from_wordnet(&mut self, wordnet: &Wordnet) {
+        self.tag(
+            wordnet
+                .lexnames()
+                .filter_map(|(lemma, lexname)| Some((lemma, Tag::from_lexname(lexname)?))),
+        );
+    }
+
+    /// Search the lexicon for entries matching a pattern.
+    pub fn search<'a, M>(&'a self, pattern: M) -> Results<'a>
+    where
+        M: Matches + Into<SearchKey> + Clone + 'a,
+    {
+        Results::new(pattern, self)
+    }
+
+    /// Search the lexicon for entries matching a pattern, using all the threads in `pool`.
+    /// This finishes sooner than [`search`](Self::search) on a multi-core machine, with the
+    /// words in the same order, but it can't stop early.
+    pub fn par_search<'a, M>(&'a self, pattern: M, pool: &ThreadPool) -> Results<'a>
+    where
+        M: Matches + Into<SearchKey> + Clone + Sync + 'a,
+    {
+        Results::par_new(pattern, self, pool)
+    }
+
     /// Search the lexicon for entries matching an anagram query.
-    pub fn anagram<'a>(&'a self, query: &str) -> Results<'a> {
-        let pat = Anagram::parse(query);
-        Results::new(pat, self)
+    pub fn anagram<'a>(&'a self, query: &str) -> Result<Results<'a>, ParseError> {
+        let pat = Anagram::parse(query)?;
+        Ok(Results::new(pat, self))
     }
 
     /// Search the lexicon for entries matching a Find Word query.
-    pub fn find_word<'a>(&'a self, query: &'a str) -> Results<'a> {
-        let pat = FindWord::parse(query);
-        Results::new(pat, self)
+    pub fn find_word<'a>(&'a self, query: &'a str) -> Result<Results<'a>, ParseError> {
+        let pat = FindWord::parse(query)?;
+        Ok(Results::new(pat, self))
+    }
+
+    /// Lists the inflections of a base form as each of the given word types, keeping only the
+    /// ones that are in the lexicon.
+    pub fn inflect(&self, base_form: &str, word_types: &[WordType]) -> Vec<Inflected> {
+        word_types
+            .iter()
+            .flat_map(|&word_type| inflections(base_form, word_type))
+            .filter(|candidate| self.contains(&candidate.word))
+            .collect()
     }
 }
 
@@ -60,25 +301,43 @@ impl Lexicon {
 /// This struct is created by the [`entries`] method on [`Lexicon`].
 /// See its documentation for more.
 pub struct Entries<'a> {
-    it: slice::Iter<'a, Entry>,
+    lexicon: &'a Lexicon,
+    indexes: Range<usize>,
 }
 
 impl<'a> Iterator for Entries<'a> {
-    type Item = &'a Entry;
+    type Item = Entry<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.it.next()
+        self.indexes.next().map(|i| self.lexicon.entry(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.indexes.size_hint()
     }
 }
 
+impl ExactSizeIterator for Entries<'_> {}
+
+/// The range of entry `i`'s part of a buffer, given the offsets of every entry's part.
+fn span(offsets: &[u32], i: usize) -> Range<usize> {
+    offsets[i] as usize..offsets[i + 1] as usize
+}
+
+/// Converts a buffer length to an offset. A lexicon with 4GB of words would be a surprise.
+fn offset(len: usize) -> u32 {
+    u32::try_from(len).expect("Lexicon is too big")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use itertools::Itertools;
 
     #[test]
     pub fn anagram_search() {
         let lex = Lexicon::new(vec!["ace", "café", "efface", "face", "fact", "fake"].into_iter());
-        let results = lex.anagram("acef");
+        let results = lex.anagram("acef").unwrap();
         assert_eq!("4", results.search_key_len());
         assert_eq!("acef", results.search_string());
         assert_eq!(
@@ -90,7 +349,7 @@ mod test {
     #[test]
     pub fn anagram_search_missing_letters() {
         let lex = Lexicon::new(vec!["ace", "café", "efface", "face", "fact", "fake"].into_iter());
-        let results = lex.anagram("ac..");
+        let results = lex.anagram("ac..").unwrap();
         assert_eq!("4", results.search_key_len());
         assert_eq!("ac..", results.search_string());
         assert_eq!(
@@ -99,10 +358,134 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn search_by_tag() {
+        let mut lex = Lexicon::new(vec!["Aachen", "aardvark", "Aaron", "acorn"].into_iter());
+        lex.tag(vec![
+            ("aachen", Tag::Location),
+            ("aardvark", Tag::Animal),
+            ("aaron", Tag::Person),
+        ]);
+        let places: Tags = vec![Tag::Location].into_iter().collect();
+        let results = lex.search(Tagged::new(FindWord::parse("a....").unwrap(), places));
+        assert_eq!(Vec::<&str>::new(), results.collect::<Vec<_>>());
+        let results = lex.search(Tagged::new(FindWord::parse("a.....").unwrap(), places));
+        assert_eq!(vec!["Aachen"], results.collect::<Vec<_>>());
+
+        let proper: Tags = vec![Tag::ProperNoun].into_iter().collect();
+        let results = lex.search(Tagged::new(Anagram::parse(".....").unwrap(), proper));
+        assert_eq!("5", results.search_key_len());
+        assert_eq!(vec!["Aaron"], results.collect::<Vec<_>>());
+    }
+
+    #[test]
+    pub fn results_are_lazy() {
+        let lex = Lexicon::new(vec!["ace", "café", "efface", "face", "fact", "fake"].into_iter());
+        let results = lex.find_word("....").unwrap();
+        assert_eq!(results.count(), 4);
+        let results = lex
+            .find_word("....")
+            .unwrap()
+            .retain(|word| word.starts_with('f'));
+        assert_eq!("4", results.search_key_len());
+        assert_eq!(results.take(2).collect::<Vec<_>>(), vec!["face", "fact"]);
+    }
+
+    #[test]
+    pub fn parallel_search_keeps_order() {
+        let letters = b'a'..=b'z';
+        let words: Vec<String> = letters
+            .clone()
+            .cartesian_product(letters.clone())
+            .cartesian_product(letters)
+            .map(|((a, b), c)| String::from_utf8(vec![b'q', a, b, c]).unwrap())
+            .collect();
+        let lex = Lexicon::new(words.iter().map(String::as_str));
+        let pattern = FindWord::parse("q.b.").unwrap();
+        let serial: Vec<_> = lex.search(pattern.clone()).collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let parallel = lex.par_search(pattern, &pool);
+        assert_eq!("4", parallel.search_key_len());
+        assert_eq!(serial.len(), 26 * 26);
+        assert_eq!(parallel.collect::<Vec<_>>(), serial);
+    }
+
+    #[test]
+    pub fn entries_match_words() {
+        let words = vec![
+            "Île-de-France",
+            "bee's knees",
+            "",
+            "zzzzzzzzzzzzzzzzz",
+            "café",
+        ];
+        let mut lex = Lexicon::new(words.iter().copied());
+        lex.tag(vec![("cafe", Tag::Food)]);
+        assert_eq!(lex.len(), words.len());
+        let entries: Vec<Entry> = lex.entries().collect();
+        for (entry, &word) in entries.iter().zip(&words) {
+            if word == "café" {
+                assert_eq!(entry.tags(), vec![Tag::Food].into_iter().collect());
+                assert_eq!(entry.word(), word);
+            } else {
+                assert_eq!(entry, &Entry::from(word));
+            }
+        }
+        assert_eq!(entries[3].letter_counts(), None);
+    }
+
+    #[test]
+    pub fn contains_words() {
+        let lex = Lexicon::new(vec!["it's", "Aachen", "face", "café", "ace"].into_iter());
+        assert!(lex.contains("its"));
+        assert!(lex.contains("AACHEN"));
+        assert!(lex.contains("cafe"));
+        assert!(lex.contains("ace"));
+        assert!(!lex.contains("aces"));
+        assert!(!lex.contains("fac"));
+    }
+
+    #[test]
+    pub fn tags_of_words() {
+        let mut lex = Lexicon::new(vec!["Aachen", "nice", "Nice", "New York"].into_iter());
+        lex.tag(vec![("nice", Tag::Location)]);
+        assert_eq!(
+            lex.word_tags("new york"),
+            vec![Tag::ProperNoun].into_iter().collect()
+        );
+        assert_eq!(
+            lex.word_tags("nice"),
+            vec![Tag::ProperNoun, Tag::Location].into_iter().collect()
+        );
+        assert_eq!(lex.word_tags("paris"), Tags::default());
+    }
+
+    #[test]
+    pub fn inflect_checks_lexicon() {
+        let lex = Lexicon::new(vec!["change", "changed", "changes", "changing"].into_iter());
+        let words = lex
+            .inflect("change", &[WordType::Noun, WordType::Verb])
+            .into_iter()
+            .map(|i| (i.word, i.word_type))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            words,
+            vec![
+                ("changes".to_string(), WordType::Noun),
+                ("changes".to_string(), WordType::Verb),
+                ("changed".to_string(), WordType::Verb),
+                ("changing".to_string(), WordType::Verb),
+            ]
+        );
+    }
+
     #[test]
     pub fn find_word() {
         let lex = Lexicon::new(vec!["ace", "café", "efface", "face", "fact", "fake"].into_iter());
-        let results = lex.find_word(".a.e");
+        let results = lex.find_word(".a.e").unwrap();
         assert_eq!("4", results.search_key_len());
         assert_eq!(".a.e", results.search_string());
         assert_eq!(
@@ -1,11 +1,24 @@
-use crate::{AsciiString, SortedAscii, WordBreaks};
+use super::{Tag, Tags};
+use crate::{AsciiString, LetterCounts, SortedAscii, WordBreaks};
+use itertools::Itertools;
+use std::borrow::Cow;
+use std::iter;
 
 /// A word or phrase stored in the Lexicon.
 ///
 /// As well as the word or phrase itself, the Entry also contains:
 ///   * The letters in their original order, stripped of accents and converted to lower case
 ///   * The letters in alphabetical order -- this makes anagram search go faster
+///   * How many times each letter appears, which makes it faster still
 ///   * The letter positions of word breaks.
+///   * Tags saying what kind of word it is. Capitalized entries are tagged as proper nouns;
+///     other tags are added by [`Lexicon::tag`].
+///
+/// An Entry made with `Entry::from` works all of this out for itself. The entries that a
+/// [`Lexicon`] hands out are views onto its storage instead, so they cost nothing to make.
+///
+/// [`Lexicon`]: crate::Lexicon
+/// [`Lexicon::tag`]: crate::Lexicon::tag
 ///
 /// # Examples
 ///
@@ -17,29 +30,57 @@ use crate::{AsciiString, SortedAscii, WordBreaks};
 /// assert_eq!("iledefrance", entry.letters().to_str());
 /// assert_eq!("acdeeefilnr", entry.sorted().to_str());
 /// assert_eq!(vec![3, 5], entry.breaks().to_vec());
+/// assert!(entry.tags().contains(wordfun::Tag::ProperNoun));
 /// ```
 #[derive(Debug, PartialEq, Clone)]
-pub struct Entry {
-    head_word: String,
-    letters: AsciiString,
-    sorted: SortedAscii,
+pub struct Entry<'a> {
+    head_word: Cow<'a, str>,
+    letters: Cow<'a, [u8]>,
+    sorted: Cow<'a, [u8]>,
+    counts: Option<LetterCounts>,
     breaks: WordBreaks,
+    tags: Tags,
 }
 
-impl Entry {
+impl<'a> Entry<'a> {
+    /// Makes an entry out of parts that have already been worked out, as stored in a Lexicon.
+    pub(super) fn from_parts(
+        head_word: &'a str,
+        letters: &'a [u8],
+        sorted: &'a [u8],
+        counts: Option<LetterCounts>,
+        breaks: WordBreaks,
+        tags: Tags,
+    ) -> Self {
+        Self {
+            head_word: Cow::Borrowed(head_word),
+            letters: Cow::Borrowed(letters),
+            sorted: Cow::Borrowed(sorted),
+            counts,
+            breaks,
+            tags,
+        }
+    }
+
     /// The original word or phrase, with capitalization, spacing, and so on intact
     pub fn word(&self) -> &str {
         &self.head_word
     }
 
     /// The letters in their original order, stripped of accents and converted to lower case
-    pub fn letters(&self) -> &AsciiString {
-        &self.letters
+    pub fn letters(&self) -> AsciiString<&[u8]> {
+        AsciiString::borrowed(&self.letters)
     }
 
     /// The letters in alphabetical order, including repeats.
-    pub fn sorted(&self) -> &SortedAscii {
-        &self.sorted
+    pub fn sorted(&self) -> SortedAscii<&[u8]> {
+        SortedAscii::borrowed(&self.sorted)
+    }
+
+    /// How many times each letter appears, or `None` if any letter appears too many times to
+    /// count this way. In that case, use `sorted()` instead.
+    pub fn letter_counts(&self) -> Option<LetterCounts> {
+        self.counts
     }
 
     /// The number of letters. This is simply the length of `letters()`.
@@ -52,19 +93,50 @@ impl Entry {
     pub fn breaks(&self) -> &WordBreaks {
         &self.breaks
     }
+
+    /// The lengths of the words in the entry, separated by commas, as in a crossword
+    /// enumeration.
+    ///
+    /// ```
+    /// assert_eq!(wordfun::Entry::from("bee's knees").enumeration(), "4,5");
+    /// assert_eq!(wordfun::Entry::from("aardvark").enumeration(), "8");
+    /// ```
+    pub fn enumeration(&self) -> String {
+        iter::once(0)
+            .chain(self.breaks.positions())
+            .chain(iter::once(self.word_length()))
+            .tuple_windows()
+            .filter(|(start, end)| end > start)
+            .map(|(start, end)| format!("{}", end - start))
+            .join(",")
+    }
+
+    /// The categories the entry belongs to.
+    pub fn tags(&self) -> Tags {
+        self.tags
+    }
 }
 
-impl From<&str> for Entry {
-    fn from(input: &str) -> Self {
-        let head_word = input.trim().to_string();
-        let (letters, breaks) = super::parse_word(&head_word);
-        let sorted = SortedAscii::from_bytes(letters.as_ref());
+impl<'a> From<&'a str> for Entry<'a> {
+    fn from(input: &'a str) -> Self {
+        let head_word = input.trim();
+        let (letters, breaks) = super::parse_word(head_word);
+        let letters: Vec<u8> = letters.into();
+        let mut sorted = letters.clone();
+        sorted.sort_unstable();
+        let counts = LetterCounts::from_letters(&letters);
+        let mut tags = Tags::default();
+        if head_word.starts_with(char::is_uppercase) {
+            tags.insert(Tag::ProperNoun);
+        }
 
         Self {
-            head_word,
-            letters,
-            sorted,
+            head_word: Cow::Borrowed(head_word),
+            letters: Cow::Owned(letters),
+            sorted: Cow::Owned(sorted),
+            counts,
             breaks,
+            tags,
         }
     }
 }
@@ -92,7 +164,8 @@ mod test {
         #[test]
         fn letters_is_idempotent(word: String) {
             let entry1 = Entry::from(word.as_ref());
-            let entry2 = Entry::from(entry1.letters().to_str());
+            let letters = entry1.letters();
+            let entry2 = Entry::from(letters.to_str());
             prop_assert_eq!(entry1.letters(), entry2.letters());
         }
     }
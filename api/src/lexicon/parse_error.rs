@@ -0,0 +1,37 @@
+use serde::Serialize;
+use thiserror::Error;
+
+/// The longest query we accept, in letters and blanks. Word breaks are stored in a
+/// [`WordBreaks`](crate::WordBreaks), which only has room for this many positions.
+pub const MAX_QUERY_LENGTH: usize = crate::WordBreaks::SIZE;
+
+/// Describes what was wrong with an anagram or find-word query. Positions count characters
+/// (not bytes) from the start of the query, starting at 0.
+#[derive(Debug, Error, PartialEq, Eq, Clone, Serialize)]
+#[serde(tag = "error", rename_all = "snake_case")]
+pub enum ParseError {
+    #[error("the query is empty")]
+    Empty,
+    #[error("unexpected {character:?} at position {position}")]
+    UnexpectedCharacter { character: char, position: usize },
+    #[error("word break at position {position} must come between two words")]
+    MisplacedBreak { position: usize },
+    #[error("the query has {length} letters, but the most we allow is {max}")]
+    TooLong { length: usize, max: usize },
+}
+
+impl ParseError {
+    /// Returns an error if a query of this length is empty or too long.
+    pub(super) fn check_length(length: usize) -> Result<(), Self> {
+        if length == 0 {
+            Err(ParseError::Empty)
+        } else if length > MAX_QUERY_LENGTH {
+            Err(ParseError::TooLong {
+                length,
+                max: MAX_QUERY_LENGTH,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -54,6 +54,40 @@ pub fn parse_word(word: &str) -> (AsciiString, WordBreaks) {
     (letters, WordBreaks::from_positions(&positions))
 }
 
+/// Fold a term into lowercase ASCII words separated by single spaces.
+///
+/// This uses the same rules as [`parse_word`], so accents, capitalization and apostrophes are
+/// ignored, and any run of spaces or punctuation counts as a single word break. Spelling variants
+/// of a phrase fold to the same key.
+///
+/// # Examples:
+///
+/// ```
+/// assert_eq!(wordfun::phrase_key("Jack-o'-lantern"), "jack o lantern");
+/// assert_eq!(wordfun::phrase_key("tête-à-tête"), "tete a tete");
+/// assert_eq!(wordfun::phrase_key("  Bee's knees! "), "bees knees");
+/// ```
+pub fn phrase_key(word: &str) -> String {
+    let mut key = String::with_capacity(word.len());
+    let mut pending_break = false;
+    for &b in deunicode(word).as_bytes() {
+        match char_type(b) {
+            CharType::Letter => {
+                if pending_break && !key.is_empty() {
+                    key.push(' ');
+                }
+                pending_break = false;
+                key.push(char::from(b.to_ascii_lowercase()));
+            }
+            CharType::Punctuation | CharType::WhiteSpace => {
+                pending_break = true;
+            }
+            CharType::Apostrophe | CharType::Digit | CharType::Unprintable => {}
+        }
+    }
+    key
+}
+
 /// Rough categorization of u8 values.
 #[derive(Copy, Clone, Debug)]
 pub enum CharType {
@@ -1,28 +1,75 @@
+use rayon::prelude::*;
+use rayon::ThreadPool;
+
 use super::{Lexicon, Matches, SearchKey};
 
-/// Results returned from Lexicon::anagram, containing the
-/// key used for the search, and an iterator that can return
-/// matching entries.
+/// The fewest entries each thread searches in a parallel search. Smaller pieces of work cost
+/// more to hand out than they save.
+const MIN_ENTRIES_PER_THREAD: usize = 4096;
+
+/// Results returned from [`Lexicon::search`], containing the key used for the search, and an
+/// iterator over the matching words.
+///
+/// The words are found as the iterator asks for them, so a caller that streams them out, or
+/// stops early, doesn't wait for the whole lexicon to be searched. Counting them, or picking the
+/// most popular with [`Popularity::top`], doesn't need them all in memory either.
+///
+/// [`Popularity::top`]: crate::Popularity::top
 pub struct Results<'a> {
-    pub words: Vec<&'a str>,
     pub key: SearchKey,
+    words: Box<dyn Iterator<Item = &'a str> + 'a>,
 }
 
 impl<'a> Results<'a> {
     pub fn new<M>(pattern: M, lexicon: &'a Lexicon) -> Self
     where
-        M: Matches + Into<SearchKey>,
+        M: Matches + Into<SearchKey> + Clone + 'a,
     {
-        let words: Vec<_> = lexicon
-            .entries()
-            .filter(|e| pattern.matches(e))
-            .map(|e| e.word())
-            .collect();
+        let key = pattern.clone().into();
+        let words = (0..lexicon.len())
+            .filter(move |&i| pattern.matches(&lexicon.entry(i)))
+            .map(move |i| lexicon.word(i));
+
+        Self {
+            key,
+            words: Box::new(words),
+        }
+    }
 
-        let key = pattern.into();
+    /// Searches the lexicon like [`new`](Self::new), but splits the entries across the threads
+    /// in `pool`. The words are in the same order as a single-threaded search, but none of them
+    /// are available until the whole lexicon has been searched.
+    pub fn par_new<M>(pattern: M, lexicon: &'a Lexicon, pool: &ThreadPool) -> Self
+    where
+        M: Matches + Into<SearchKey> + Clone + Sync + 'a,
+    {
+        let words: Vec<&str> = pool.install(|| {
+            (0..lexicon.len())
+                .into_par_iter()
+                .with_min_len(MIN_ENTRIES_PER_THREAD)
+                .filter(|&i| pattern.matches(&lexicon.entry(i)))
+                .map(|i| lexicon.word(i))
+                .collect()
+        });
 
-        Self { words, key }
+        Self {
+            key: pattern.into(),
+            words: Box::new(words.into_iter()),
+        }
     }
+
+    /// Narrows the results down to the words for which `predicate` returns true, keeping the
+    /// same search key.
+    pub fn retain<F>(self, mut predicate: F) -> Self
+    where
+        F: FnMut(&str) -> bool + 'a,
+    {
+        Self {
+            key: self.key,
+            words: Box::new(self.words.filter(move |word| predicate(word))),
+        }
+    }
+
     pub fn search_key_len(&self) -> &str {
         self.key.search_len()
     }
@@ -32,11 +79,10 @@ impl<'a> Results<'a> {
     }
 }
 
-impl<'a> IntoIterator for Results<'a> {
+impl<'a> Iterator for Results<'a> {
     type Item = &'a str;
-    type IntoIter = std::vec::IntoIter<&'a str>;
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.words.into_iter()
+    fn next(&mut self) -> Option<Self::Item> {
+        self.words.next()
     }
 }
@@ -1,39 +1,68 @@
-use deunicode::deunicode;
+use deunicode::deunicode_char;
 use itertools::Itertools;
 
-use super::{Entry, SearchKey, SortedAscii, WordBreaks};
+use super::{Entry, LetterCounts, ParseError, SearchKey, SortedAscii, Tags, WordBreaks};
+
+/// Returns the lowercase ASCII letters for a character, such as "e" for "É" or "ae" for "æ",
+/// or `None` if it isn't a letter.
+fn ascii_letters(ch: char) -> Option<impl Iterator<Item = u8>> {
+    deunicode_char(ch)
+        .filter(|ascii| !ascii.is_empty() && ascii.bytes().all(|b| b.is_ascii_alphabetic()))
+        .map(|ascii| ascii.bytes().map(|b| b.to_ascii_lowercase()))
+}
+
+/// Returns true for characters we skip over in a query, like the spaces and apostrophes in
+/// anagram fodder such as "bee's knees".
+fn is_separator(ch: char) -> bool {
+    ch.is_whitespace() || matches!(ch, '\'' | '’' | '-' | ',')
+}
 
 pub trait Matches {
     fn matches(&self, entry: &Entry) -> bool;
 }
 
+#[derive(Clone)]
 pub struct Anagram {
     sorted: SortedAscii,
+    counts: Option<LetterCounts>,
     num_blanks: usize,
     original: String,
 }
 
 impl Anagram {
-    pub fn parse(input: &str) -> Self {
+    /// Parses anagram fodder, where "." stands for an unknown letter. Spaces and punctuation
+    /// between words are ignored.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
         let mut num_blanks = 0;
         let mut letters = Vec::new();
-        for &b in deunicode(input).as_bytes() {
-            if b == b'.' {
+        for (position, ch) in input.chars().enumerate() {
+            if ch == '.' {
                 num_blanks += 1;
-            } else if b.is_ascii_alphabetic() {
-                letters.push(b.to_ascii_lowercase())
+            } else if let Some(ascii) = ascii_letters(ch) {
+                letters.extend(ascii);
+            } else if !is_separator(ch) {
+                return Err(ParseError::UnexpectedCharacter {
+                    character: ch,
+                    position,
+                });
             }
         }
-        Self {
+        ParseError::check_length(letters.len() + num_blanks)?;
+        Ok(Self {
             sorted: SortedAscii::from_bytes(&letters),
+            counts: LetterCounts::from_letters(&letters),
             num_blanks,
             original: input.to_string(),
-        }
+        })
     }
 
     pub fn len(&self) -> usize {
         self.sorted.len() + self.num_blanks
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 impl Matches for Anagram {
@@ -42,7 +71,11 @@ impl Matches for Anagram {
             return false;
         }
 
-        self.sorted.is_subset(entry.sorted())
+        // Counting letters is quicker, but only works for up to 15 of each letter.
+        match (self.counts, entry.letter_counts()) {
+            (Some(ours), Some(theirs)) => ours.is_subset(theirs),
+            _ => self.sorted.is_subset(&entry.sorted()),
+        }
     }
 }
 
@@ -55,7 +88,7 @@ impl From<Anagram> for SearchKey {
     }
 }
 
-#[derive(Default, Debug, PartialEq)]
+#[derive(Default, Debug, PartialEq, Clone)]
 pub struct FindWord {
     display: String,
     pat: Vec<u8>,
@@ -63,25 +96,46 @@ pub struct FindWord {
 }
 
 impl FindWord {
-    pub fn parse(query: &str) -> Self {
+    /// Parses a find-word pattern, where "." stands for an unknown letter and "/" separates
+    /// words. Spaces are ignored.
+    pub fn parse(query: &str) -> Result<Self, ParseError> {
         let mut pat = Vec::new();
         let mut positions = Vec::new();
         let mut display = String::new();
-        for &b in deunicode(query).as_bytes() {
-            if b == b'.' || b.is_ascii_alphabetic() {
-                pat.push(b.to_ascii_lowercase());
-                display.push(char::from(b.to_ascii_lowercase()));
-            } else if b == b'/' {
+        let mut last_break = None;
+        for (position, ch) in query.chars().enumerate() {
+            if ch == '.' {
+                pat.push(b'.');
+                display.push(ch);
+            } else if let Some(ascii) = ascii_letters(ch) {
+                for b in ascii {
+                    pat.push(b);
+                    display.push(char::from(b));
+                }
+            } else if ch == '/' {
+                if pat.is_empty() || positions.last() == Some(&pat.len()) {
+                    return Err(ParseError::MisplacedBreak { position });
+                }
                 positions.push(pat.len());
-                display.push(char::from(b));
+                display.push(ch);
+                last_break = Some(position);
+            } else if !ch.is_whitespace() {
+                return Err(ParseError::UnexpectedCharacter {
+                    character: ch,
+                    position,
+                });
             }
         }
+        if let Some(position) = last_break.filter(|_| positions.last() == Some(&pat.len())) {
+            return Err(ParseError::MisplacedBreak { position });
+        }
+        ParseError::check_length(pat.len())?;
 
-        Self {
+        Ok(Self {
             display,
             pat,
             breaks: WordBreaks::from_positions(&positions),
-        }
+        })
     }
 }
 
@@ -118,13 +172,38 @@ impl From<FindWord> for SearchKey {
     }
 }
 
+/// Narrows another pattern down to entries with at least one of the given tags.
+#[derive(Clone)]
+pub struct Tagged<M> {
+    pattern: M,
+    tags: Tags,
+}
+
+impl<M: Matches> Tagged<M> {
+    pub fn new(pattern: M, tags: Tags) -> Self {
+        Self { pattern, tags }
+    }
+}
+
+impl<M: Matches> Matches for Tagged<M> {
+    fn matches(&self, entry: &Entry) -> bool {
+        entry.tags().intersects(self.tags) && self.pattern.matches(entry)
+    }
+}
+
+impl<M: Into<SearchKey>> From<Tagged<M>> for SearchKey {
+    fn from(tagged: Tagged<M>) -> SearchKey {
+        tagged.pattern.into()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
     #[test]
     pub fn parse_anagram() {
-        let search = Anagram::parse("retain");
+        let search = Anagram::parse("retain").unwrap();
         assert_eq!("aeinrt", search.sorted.to_str());
         assert_eq!(0, search.num_blanks);
         assert_eq!("retain", search.original);
@@ -132,7 +211,7 @@ mod test {
 
     #[test]
     pub fn parse_anagram_blanks() {
-        let search = Anagram::parse("r.t.in");
+        let search = Anagram::parse("r.t.in").unwrap();
         assert_eq!("inrt", search.sorted.to_str());
         assert_eq!(2, search.num_blanks);
         assert_eq!("r.t.in", search.original);
@@ -140,7 +219,7 @@ mod test {
 
     #[test]
     pub fn parse_anagram_accents() {
-        let search = Anagram::parse("café");
+        let search = Anagram::parse("café").unwrap();
         assert_eq!("acef", search.sorted.to_str());
         assert_eq!(0, search.num_blanks);
         assert_eq!("café", search.original);
@@ -148,16 +227,31 @@ mod test {
 
     #[test]
     pub fn parse_anagram_case() {
-        let search = Anagram::parse("Pioneering tsar");
+        let search = Anagram::parse("Pioneering tsar").unwrap();
         assert_eq!("aeegiinnoprrst", search.sorted.to_str());
         assert_eq!(0, search.num_blanks);
         assert_eq!("Pioneering tsar", search.original);
     }
 
+    #[test]
+    pub fn anagram_with_many_repeats() {
+        // Too many of one letter to count, so this falls back to sorted letters.
+        let buzz = "z".repeat(16);
+        assert!(Anagram::parse(&buzz)
+            .unwrap()
+            .matches(&Entry::from(buzz.as_str())));
+        assert!(!Anagram::parse(&format!("{}.", &buzz[1..]))
+            .unwrap()
+            .matches(&Entry::from(format!("{}a", buzz).as_str())));
+        assert!(Anagram::parse("r.t.in")
+            .unwrap()
+            .matches(&Entry::from("retain")));
+    }
+
     #[test]
     pub fn parse_pattern_with_letters() {
         assert_eq!(
-            FindWord::parse(".a.e"),
+            FindWord::parse(".a.e").unwrap(),
             FindWord {
                 display: ".a.e".to_string(),
                 pat: b".a.e".to_vec(),
@@ -169,7 +263,7 @@ mod test {
     #[test]
     pub fn parse_pattern_dotdotdot() {
         assert_eq!(
-            FindWord::parse("..."),
+            FindWord::parse("...").unwrap(),
             FindWord {
                 display: "...".to_string(),
                 pat: b"...".to_vec(),
@@ -181,7 +275,7 @@ mod test {
     #[test]
     pub fn parse_pattern_slashes() {
         assert_eq!(
-            FindWord::parse("h.r./...l../e.g"),
+            FindWord::parse("h.r./...l../e.g").unwrap(),
             FindWord {
                 display: "h.r./...l../e.g".to_string(),
                 pat: b"h.r....l..e.g".to_vec(),
@@ -192,7 +286,9 @@ mod test {
 
     #[test]
     pub fn pattern_matches() {
-        assert!(FindWord::parse(".a.e").matches(&Entry::from("café")));
+        assert!(FindWord::parse(".a.e")
+            .unwrap()
+            .matches(&Entry::from("café")));
     }
 
     #[test]
@@ -200,7 +296,7 @@ mod test {
         // A pattern with one break only matches an entry with
         // one break in the same place.
         // s.e./..... should match see reason but not see no evil
-        let pattern = FindWord::parse("s.e/......");
+        let pattern = FindWord::parse("s.e/......").unwrap();
 
         assert!(!pattern.matches(&Entry::from("scenarios")));
         assert!(pattern.matches(&Entry::from("see reason")));
@@ -211,10 +307,55 @@ mod test {
     pub fn match_pattern_with_two_breaks() {
         // A pattern specifying 2 breaks has to match
         // entries with exactly 2 breaks.
-        let pattern = FindWord::parse("s.e/../....");
+        let pattern = FindWord::parse("s.e/../....").unwrap();
 
         assert!(!pattern.matches(&Entry::from("scenarios")));
         assert!(!pattern.matches(&Entry::from("see reason")));
         assert!(pattern.matches(&Entry::from("see no evil")));
     }
+
+    #[test]
+    pub fn reject_unexpected_characters() {
+        assert_eq!(
+            Anagram::parse("ret4in").err(),
+            Some(ParseError::UnexpectedCharacter {
+                character: '4',
+                position: 3
+            })
+        );
+        assert_eq!(
+            FindWord::parse("é.?").err(),
+            Some(ParseError::UnexpectedCharacter {
+                character: '?',
+                position: 2
+            })
+        );
+        assert!(Anagram::parse("bee's knees, café").is_ok());
+    }
+
+    #[test]
+    pub fn reject_misplaced_breaks() {
+        for (query, position) in &[("/...", 0), ("...//..", 4), ("../", 2)] {
+            assert_eq!(
+                FindWord::parse(query).err(),
+                Some(ParseError::MisplacedBreak {
+                    position: *position
+                })
+            );
+        }
+    }
+
+    #[test]
+    pub fn reject_empty_and_long_queries() {
+        assert_eq!(Anagram::parse(" ").err(), Some(ParseError::Empty));
+        assert_eq!(FindWord::parse("").err(), Some(ParseError::Empty));
+        assert!(FindWord::parse(&".".repeat(60)).is_ok());
+        assert_eq!(
+            FindWord::parse(&".".repeat(61)).err(),
+            Some(ParseError::TooLong {
+                length: 61,
+                max: 60
+            })
+        );
+    }
 }
@@ -1,3 +1,8 @@
+use serde::Serialize;
+
+/// The query as it was understood, for echoing back in responses, along with the lengths of
+/// the words it looks for, such as "4,5".
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct SearchKey {
     pub search_string: String,
     pub len: String,
@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::iter::FromIterator;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// A category that a lexicon entry can belong to.
+///
+/// `ProperNoun` comes from capitalization. The rest are Wordnet's lexicographer files for
+/// nouns, so that "Aachen" is tagged `Location` and "aardvark" is tagged `Animal`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Tag {
+    ProperNoun,
+    Act,
+    Animal,
+    Artifact,
+    Attribute,
+    Body,
+    Cognition,
+    Communication,
+    Event,
+    Feeling,
+    Food,
+    Group,
+    Location,
+    Motive,
+    Object,
+    Person,
+    Phenomenon,
+    Plant,
+    Possession,
+    Process,
+    Quantity,
+    Relation,
+    Shape,
+    State,
+    Substance,
+    Time,
+}
+
+impl Tag {
+    pub const ALL: [Tag; 26] = [
+        Tag::ProperNoun,
+        Tag::Act,
+        Tag::Animal,
+        Tag::Artifact,
+        Tag::Attribute,
+        Tag::Body,
+        Tag::Cognition,
+        Tag::Communication,
+        Tag::Event,
+        Tag::Feeling,
+        Tag::Food,
+        Tag::Group,
+        Tag::Location,
+        Tag::Motive,
+        Tag::Object,
+        Tag::Person,
+        Tag::Phenomenon,
+        Tag::Plant,
+        Tag::Possession,
+        Tag::Process,
+        Tag::Quantity,
+        Tag::Relation,
+        Tag::Shape,
+        Tag::State,
+        Tag::Substance,
+        Tag::Time,
+    ];
+
+    /// The tag's name, as used in queries and JSON.
+    pub fn name(self) -> &'static str {
+        match self {
+            Tag::ProperNoun => "proper_noun",
+            Tag::Act => "act",
+            Tag::Animal => "animal",
+            Tag::Artifact => "artifact",
+            Tag::Attribute => "attribute",
+            Tag::Body => "body",
+            Tag::Cognition => "cognition",
+            Tag::Communication => "communication",
+            Tag::Event => "event",
+            Tag::Feeling => "feeling",
+            Tag::Food => "food",
+            Tag::Group => "group",
+            Tag::Location => "location",
+            Tag::Motive => "motive",
+            Tag::Object => "object",
+            Tag::Person => "person",
+            Tag::Phenomenon => "phenomenon",
+            Tag::Plant => "plant",
+            Tag::Possession => "possession",
+            Tag::Process => "process",
+            Tag::Quantity => "quantity",
+            Tag::Relation => "relation",
+            Tag::Shape => "shape",
+            Tag::State => "state",
+            Tag::Substance => "substance",
+            Tag::Time => "time",
+        }
+    }
+
+    /// Converts the name of a Wordnet lexicographer file, such as "noun.location", to a tag.
+    /// Verb and adjective files, and the catch-all "noun.Tops", don't have tags.
+    pub fn from_lexname(lexname: &str) -> Option<Self> {
+        let name = lexname.strip_prefix("noun.")?;
+        Self::ALL
+            .iter()
+            .skip(1)
+            .copied()
+            .find(|tag| tag.name() == name)
+    }
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("unknown category {0:?}")]
+pub struct UnknownTag(String);
+
+impl FromStr for Tag {
+    type Err = UnknownTag;
+
+    /// Parses a tag from its name ("location") or its Wordnet lexicographer file
+    /// ("noun.location").
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|tag| tag.name() == name)
+            .or_else(|| Self::from_lexname(&name))
+            .ok_or_else(|| UnknownTag(s.to_string()))
+    }
+}
+
+/// A set of [`Tag`]s.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Tags(u32);
+
+impl Tags {
+    pub fn insert(&mut self, tag: Tag) {
+        self.0 |= Self::bit(tag);
+    }
+
+    pub fn contains(self, tag: Tag) -> bool {
+        self.0 & Self::bit(tag) != 0
+    }
+
+    /// Returns true if the two sets have any tags in common.
+    pub fn intersects(self, other: Tags) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: Tags) -> Tags {
+        Tags(self.0 | other.0)
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Tag> {
+        Tag::ALL
+            .iter()
+            .copied()
+            .filter(move |&tag| self.contains(tag))
+    }
+
+    fn bit(tag: Tag) -> u32 {
+        1 << tag as u32
+    }
+}
+
+impl FromIterator<Tag> for Tags {
+    fn from_iter<I: IntoIterator<Item = Tag>>(iter: I) -> Self {
+        let mut tags = Tags::default();
+        for tag in iter {
+            tags.insert(tag);
+        }
+        tags
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn parse_tags() {
+        assert_eq!("location".parse::<Tag>().unwrap(), Tag::Location);
+        assert_eq!("Proper_Noun".parse::<Tag>().unwrap(), Tag::ProperNoun);
+        assert_eq!("noun.animal".parse::<Tag>().unwrap(), Tag::Animal);
+        assert!("verb.motion".parse::<Tag>().is_err());
+        assert_eq!(Tag::from_lexname("noun.Tops"), None);
+    }
+
+    #[test]
+    pub fn tag_sets() {
+        let tags: Tags = vec![Tag::Animal, Tag::Food].into_iter().collect();
+        assert!(tags.contains(Tag::Food));
+        assert!(!tags.contains(Tag::Location));
+        assert!(tags.intersects(vec![Tag::Food, Tag::Plant].into_iter().collect()));
+        assert!(!tags.intersects(Tags::default()));
+        assert_eq!(
+            tags.iter().collect::<Vec<_>>(),
+            vec![Tag::Animal, Tag::Food]
+        );
+    }
+}
@@ -9,19 +9,33 @@
 
 mod ascii_string;
 mod dictionary;
+mod letter_counts;
 mod lexicon;
+mod past_answers;
 mod plural;
 mod popularity;
+mod reverse_dictionary;
+mod snapshot;
 mod sorted_ascii;
 mod thesaurus;
 mod word_breaks;
 mod wordnet;
 
 pub use ascii_string::AsciiString;
-pub use dictionary::Dictionary;
-pub use lexicon::{parse_word, Entry, Lexicon, Results, SearchKey};
+pub use dictionary::{
+    Definition, Dictionary, DictionarySource, Glossary, Precedence, SourceEntry, SourceError,
+};
+pub use letter_counts::LetterCounts;
+pub use lexicon::{
+    parse_word, phrase_key, Anagram, Entry, FindWord, Lexicon, Matches, ParseError, Results,
+    SearchKey, Tag, Tagged, Tags, MAX_QUERY_LENGTH,
+};
+pub use past_answers::{Appearance, PastAnswers, PastAnswersError};
 pub use plural::plural;
-pub use popularity::{Popularity, Ranked};
+pub use popularity::{FrequencyError, FrequencyList, Popularity, Ranked};
+pub use reverse_dictionary::{ReverseDictionary, ReverseMatch};
+pub use snapshot::{DataSource, DataSources, Snapshot, SnapshotError, SNAPSHOT_VERSION};
 pub use sorted_ascii::SortedAscii;
-pub use thesaurus::Thesaurus;
+pub use thesaurus::{Expansion, Source, Synonym, Thesaurus};
 pub use word_breaks::WordBreaks;
+pub use wordnet::{inflections, Inflected, Inflection, WordType, Wordnet, WordnetError};
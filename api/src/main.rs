@@ -3,7 +3,9 @@ mod web;
 use anyhow::Result;
 use env_logger::Env;
 use std::path::PathBuf;
+use std::str::FromStr;
 use structopt::StructOpt;
+use thiserror::Error;
 
 #[derive(Debug, Clone, StructOpt)]
 pub struct Options {
@@ -22,10 +24,163 @@ pub struct Options {
     /// API.
     #[structopt(short = "d", long = "assets-dir")]
     pub assets_dir: Option<PathBuf>,
+
+    /// Extra dictionaries to layer on top of Wordnet, such as a glossary of crossword-ese.
+    /// Each can be a .tsv, a Wiktextract .jsonl, or a dictd .index file. Their definitions are
+    /// preferred to Wordnet's, and earlier glossaries are preferred to later ones.
+    #[structopt(short = "g", long = "glossary")]
+    pub glossaries: Vec<PathBuf>,
+
+    /// Word frequency lists to sort results by, alongside Wordnet's tag counts. Each file has a
+    /// word and a count on each line. Add "=WEIGHT" to the path to change how much the list
+    /// counts for; the weight must be positive, and the default, like Wordnet's, is 1.
+    #[structopt(short = "f", long = "frequency-list")]
+    pub frequency_lists: Vec<WeightedPath>,
+
+    /// A CSV file of answers from past puzzles, with the columns answer, clue, publication, and
+    /// date. Search results say how often each answer has appeared, with some of its clues, and
+    /// past answers count towards popularity.
+    #[structopt(long = "past-answers")]
+    pub past_answers: Option<PathBuf>,
+
+    /// How many threads each anagram or find-word search is split across. The default of 1
+    /// searches on the request's own thread; 0 means one thread per CPU core. Streamed
+    /// searches always use one thread, so they can send matches as they find them.
+    #[structopt(long = "search-threads", default_value = "1")]
+    pub search_threads: usize,
+
+    #[structopt(flatten)]
+    pub data: DataOptions,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Where the reference data comes from. Each path can also be set in a config file; a path given
+/// as a flag takes precedence.
+#[derive(Debug, Clone, StructOpt)]
+pub struct DataOptions {
+    /// A TOML file setting any of data_dir, lexicon, popular_words, thesaurus, wordnet_dir and
+    /// index. Relative paths in the file are relative to the file's directory.
+    #[structopt(long = "config")]
+    pub config: Option<PathBuf>,
+
+    /// The directory that lexicon.txt, popular_words.txt and wordfun.index are in, unless
+    /// their own paths are given. The default is "data".
+    #[structopt(long = "data-dir")]
+    pub data_dir: Option<PathBuf>,
+
+    /// The list of words and phrases to search, one per line.
+    #[structopt(long = "lexicon")]
+    pub lexicon: Option<PathBuf>,
+
+    /// Popular words, one per line, most popular first.
+    #[structopt(long = "popular-words")]
+    pub popular_words: Option<PathBuf>,
+
+    /// A thesaurus file with a comma-separated list of synonyms on each line. If no file is
+    /// specified, the bundled thesaurus is used.
+    #[structopt(long = "thesaurus")]
+    pub thesaurus: Option<PathBuf>,
+
+    /// A Wordnet "dict" directory to read definitions, categories and synonyms from. If no
+    /// directory is specified, the bundled copy of Wordnet is used.
+    #[structopt(long = "wordnet-dir")]
+    pub wordnet_dir: Option<PathBuf>,
+
+    /// A binary index of the lexicon, dictionary, thesaurus and popular words, made by the
+    /// build-index command. If it exists, the server loads it instead of parsing the data
+    /// files, which is much quicker. If it's missing, from another version, or was built from
+    /// other data files or older copies of them, the server falls back to the data files.
+    #[structopt(long = "index")]
+    pub index: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub enum Command {
+    /// Builds the binary index from the data files and writes it to the --index path. Run this
+    /// again whenever the data changes.
+    BuildIndex,
+}
+
+/// A path with an optional weight, written as "PATH" or "PATH=WEIGHT". The weight must be a
+/// positive number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightedPath {
+    pub path: PathBuf,
+    pub weight: f64,
+}
+
+#[derive(Debug, Error)]
+#[error("expected PATH[=WEIGHT], with a positive WEIGHT, but got {0:?}")]
+pub struct BadWeightedPath(String);
+
+impl FromStr for WeightedPath {
+    type Err = BadWeightedPath;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rfind('=') {
+            Some(pos) => {
+                let weight = s[pos + 1..]
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|weight| weight.is_finite() && *weight > 0.0)
+                    .ok_or_else(|| BadWeightedPath(s.to_string()))?;
+                Ok(Self {
+                    path: PathBuf::from(&s[..pos]),
+                    weight,
+                })
+            }
+            None => Ok(Self {
+                path: PathBuf::from(s),
+                weight: 1.0,
+            }),
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let opts = Options::from_args();
     env_logger::from_env(Env::default().default_filter_or("info")).init();
-    web::serve(opts)
+    match opts.command {
+        Some(Command::BuildIndex) => web::build_index(&opts),
+        None => web::serve(opts),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn parse_weighted_path() {
+        let weighted = |path: &str, weight| WeightedPath {
+            path: PathBuf::from(path),
+            weight,
+        };
+        assert_eq!(
+            "counts.tsv".parse::<WeightedPath>().unwrap(),
+            weighted("counts.tsv", 1.0)
+        );
+        assert_eq!(
+            "a=b.tsv=0.5".parse::<WeightedPath>().unwrap(),
+            weighted("a=b.tsv", 0.5)
+        );
+        for bad in &[
+            "counts=v2.tsv",
+            "counts.tsv=NaN",
+            "counts.tsv=inf",
+            "counts.tsv=-1",
+        ] {
+            let error = bad.parse::<WeightedPath>().unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!(
+                    "expected PATH[=WEIGHT], with a positive WEIGHT, but got {:?}",
+                    bad
+                )
+            );
+        }
+        assert!("counts.tsv=0".parse::<WeightedPath>().is_err());
+    }
 }
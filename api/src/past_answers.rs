@@ -0,0 +1,333 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::{parse_word, phrase_key, AsciiString, FrequencyList};
+
+#[derive(Debug, Error)]
+pub enum PastAnswersError {
+    #[error("Could not read {path:?}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Could not parse past answers")]
+    Csv(#[from] csv::Error),
+    #[error("The date of {answer:?} is {date:?}, but dates must be written as YYYY-MM-DD")]
+    BadDate { answer: String, date: String },
+}
+
+/// One published appearance of an answer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Appearance {
+    /// The answer as it was published, such as "BEES KNEES".
+    pub answer: String,
+    pub clue: String,
+    pub publication: String,
+    /// The publication date, written as YYYY-MM-DD so that dates sort in order.
+    pub date: String,
+}
+
+type AppearanceId = usize;
+
+/// Answers from past published puzzles, with their clues.
+///
+/// Answers are indexed by their letters, as in [`Entry::letters`], so "BEES KNEES" in a puzzle
+/// matches "bee's knees" in the lexicon. Clues are indexed by the words in them, folded with
+/// [`phrase_key`], so they can be searched with [`search_clues`].
+///
+/// [`Entry::letters`]: crate::Entry::letters
+/// [`search_clues`]: PastAnswers::search_clues
+#[derive(Default)]
+pub struct PastAnswers {
+    appearances: Vec<Appearance>,
+    /// The appearances of each answer, most recent first.
+    answers: HashMap<AsciiString, Vec<AppearanceId>>,
+    /// The appearances whose clues contain each word, in the order they were added.
+    clue_words: HashMap<String, Vec<AppearanceId>>,
+    /// The appearances on each date, in the order they were added.
+    dates: BTreeMap<String, Vec<AppearanceId>>,
+}
+
+impl PastAnswers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a CSV file with a header row and the columns `answer`, `clue`, `publication` and
+    /// `date`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, PastAnswersError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|source| PastAnswersError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_csv(file)
+    }
+
+    /// Reads CSV in the same format as [`open`](Self::open).
+    pub fn from_csv<R: io::Read>(reader: R) -> Result<Self, PastAnswersError> {
+        let mut past_answers = Self::new();
+        for record in csv::Reader::from_reader(reader).deserialize() {
+            past_answers.insert(record?)?;
+        }
+        Ok(past_answers)
+    }
+
+    /// Adds an appearance, as long as its date is written as YYYY-MM-DD.
+    pub fn insert(&mut self, appearance: Appearance) -> Result<(), PastAnswersError> {
+        if !is_date(&appearance.date) {
+            return Err(PastAnswersError::BadDate {
+                answer: appearance.answer,
+                date: appearance.date,
+            });
+        }
+        let id = self.appearances.len();
+        let mut words = clue_words(&appearance.clue);
+        words.sort_unstable();
+        words.dedup();
+        for word in words {
+            self.clue_words.entry(word).or_default().push(id);
+        }
+
+        let ids = self
+            .answers
+            .entry(parse_word(&appearance.answer).0)
+            .or_default();
+        // Keep the most recent first, so samples are up to date.
+        let appearances = &self.appearances;
+        // The comparison is never `Equal`, so this finds the end of the run of appearances on
+        // or after this date. (`partition_point` would say so directly, but needs Rust 1.52.)
+        let pos = ids
+            .binary_search_by(|&other| {
+                if appearances[other].date >= appearance.date {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                }
+            })
+            .unwrap_or_else(|pos| pos);
+        ids.insert(pos, id);
+        self.dates
+            .entry(appearance.date.clone())
+            .or_default()
+            .push(id);
+        self.appearances.push(appearance);
+        Ok(())
+    }
+
+    /// Returns every appearance of every answer, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &Appearance> {
+        self.appearances.iter()
+    }
+
+    /// Returns every appearance of every answer, most recent first.
+    pub fn recent(&self) -> impl Iterator<Item = &Appearance> {
+        self.dates
+            .values()
+            .rev()
+            .flatten()
+            .map(move |&id| &self.appearances[id])
+    }
+
+    /// Returns every appearance of a word, most recent first.
+    pub fn appearances<'a>(&'a self, word: &str) -> impl Iterator<Item = &'a Appearance> + 'a {
+        self.answers
+            .get(&parse_word(word).0)
+            .into_iter()
+            .flatten()
+            .map(move |&id| &self.appearances[id])
+    }
+
+    /// Returns the number of times a word has been an answer.
+    pub fn count(&self, word: &str) -> usize {
+        self.answers
+            .get(&parse_word(word).0)
+            .map_or(0, |ids| ids.len())
+    }
+
+    /// Returns up to `max` of a word's appearances, most recent first, skipping repeated clues.
+    pub fn sample_clues(&self, word: &str, max: usize) -> Vec<&Appearance> {
+        let mut samples: Vec<&Appearance> = Vec::new();
+        for appearance in self.appearances(word) {
+            if samples.len() == max {
+                break;
+            }
+            if !samples.iter().any(|sample| sample.clue == appearance.clue) {
+                samples.push(appearance);
+            }
+        }
+        samples
+    }
+
+    /// Returns the appearances whose clues contain every word in the query, most recent first.
+    /// Case, accents and punctuation are ignored, so "Coastal flier" finds "coastal flier,
+    /// perhaps".
+    pub fn search_clues(&self, query: &str) -> Vec<&Appearance> {
+        let mut postings = Vec::new();
+        for word in clue_words(query) {
+            match self.clue_words.get(&word) {
+                Some(ids) => postings.push(ids),
+                None => return Vec::new(),
+            }
+        }
+        // Walk the shortest list, and check the rest, which are sorted, by binary search.
+        postings.sort_by_key(|ids| ids.len());
+        let (first, rest) = match postings.split_first() {
+            Some(split) => split,
+            None => return Vec::new(),
+        };
+        let mut found: Vec<&Appearance> = first
+            .iter()
+            .filter(|id| rest.iter().all(|ids| ids.binary_search(id).is_ok()))
+            .map(|&id| &self.appearances[id])
+            .collect();
+        found.sort_by(|a, b| b.date.cmp(&a.date));
+        found
+    }
+
+    /// Returns how often each answer has appeared, for use in [`Popularity`].
+    ///
+    /// [`Popularity`]: crate::Popularity
+    pub fn frequency_list(&self) -> FrequencyList {
+        FrequencyList::from_counts(
+            self.answers
+                .iter()
+                .map(|(letters, ids)| (letters.to_str(), ids.len() as u64)),
+        )
+    }
+
+    /// Returns the number of distinct answers
+    pub fn len(&self) -> usize {
+        self.answers.len()
+    }
+
+    /// Returns `true` if there are no answers
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+}
+
+/// Checks that a date is written as YYYY-MM-DD, and is a day that exists, counting leap years.
+fn is_date(date: &str) -> bool {
+    let digits = |part: &str, len| part.len() == len && part.bytes().all(|b| b.is_ascii_digit());
+    match date.split('-').collect::<Vec<_>>()[..] {
+        [year, month, day] if digits(year, 4) && digits(month, 2) && digits(day, 2) => {
+            let year: u32 = year.parse().unwrap();
+            let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+            let days = match month.parse::<u8>().unwrap() {
+                2 if leap => 29,
+                2 => 28,
+                4 | 6 | 9 | 11 => 30,
+                1..=12 => 31,
+                _ => return false,
+            };
+            (1..=days).contains(&day.parse::<u8>().unwrap())
+        }
+        _ => false,
+    }
+}
+
+/// Splits clue text into words the same way [`phrase_key`] does.
+fn clue_words(text: &str) -> Vec<String> {
+    phrase_key(text)
+        .split(' ')
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CSV: &str = "answer,clue,publication,date
+ERNE,Sea eagle,Daily,2020-03-01
+BEES KNEES,The best,Daily,2019-05-12
+erne,\"Coastal flier, perhaps\",Sunday,2020-06-14
+ERNE,Sea eagle,Sunday,2018-01-07
+";
+
+    #[test]
+    pub fn count_answers_by_letters() {
+        let past = PastAnswers::from_csv(CSV.as_bytes()).unwrap();
+        assert_eq!(past.len(), 2);
+        assert_eq!(past.count("erne"), 3);
+        assert_eq!(past.count("bee's knees"), 1);
+        assert_eq!(past.count("emu"), 0);
+        assert_eq!(past.appearances("Erne").next().unwrap().date, "2020-06-14");
+    }
+
+    #[test]
+    pub fn sample_recent_distinct_clues() {
+        let past = PastAnswers::from_csv(CSV.as_bytes()).unwrap();
+        let clues: Vec<_> = past
+            .sample_clues("erne", 5)
+            .into_iter()
+            .map(|a| a.clue.as_str())
+            .collect();
+        assert_eq!(clues, vec!["Coastal flier, perhaps", "Sea eagle"]);
+        assert_eq!(past.sample_clues("erne", 1).len(), 1);
+    }
+
+    #[test]
+    pub fn search_clue_words() {
+        let past = PastAnswers::from_csv(CSV.as_bytes()).unwrap();
+        let answers = |query| {
+            past.search_clues(query)
+                .into_iter()
+                .map(|a| (a.answer.as_str(), a.date.as_str()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            answers("sea eagle"),
+            vec![("ERNE", "2020-03-01"), ("ERNE", "2018-01-07")]
+        );
+        assert_eq!(answers("FLIER coastal"), vec![("erne", "2020-06-14")]);
+        assert_eq!(answers("Sea best"), vec![]);
+        assert_eq!(answers("..."), vec![]);
+    }
+
+    #[test]
+    pub fn list_recent_first() {
+        let past = PastAnswers::from_csv(CSV.as_bytes()).unwrap();
+        let dates: Vec<_> = past.recent().map(|a| a.date.as_str()).collect();
+        assert_eq!(
+            dates,
+            vec!["2020-06-14", "2020-03-01", "2019-05-12", "2018-01-07"]
+        );
+    }
+
+    #[test]
+    pub fn reject_bad_csv() {
+        assert!(PastAnswers::from_csv("answer,clue\nERNE,Sea eagle\n".as_bytes()).is_err());
+        for date in &[
+            "1/3/2020",
+            "2020-3-1",
+            "2020-13-01",
+            "2020-03-00",
+            "2020-02-31",
+            "2021-04-31",
+            "2021-02-29",
+            "1900-02-29",
+            "20-03-01",
+        ] {
+            let csv = format!(
+                "answer,clue,publication,date\nERNE,Sea eagle,Daily,{}\n",
+                date
+            );
+            assert!(matches!(
+                PastAnswers::from_csv(csv.as_bytes()),
+                Err(PastAnswersError::BadDate { .. })
+            ));
+        }
+        for date in &["2020-02-29", "2000-02-29", "2021-12-31"] {
+            assert!(is_date(date), "{}", date);
+        }
+    }
+}
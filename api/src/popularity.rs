@@ -1,14 +1,26 @@
+mod frequency_list;
+
 use crate::AsciiString;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BinaryHeap, HashMap};
 use std::fmt;
 
 use crate::parse_word;
+use crate::wordnet::Wordnet;
+
+pub use frequency_list::{FrequencyError, FrequencyList};
 
+/// A word along with what we know about its popularity.
 #[derive(Debug)]
 pub enum Ranked<T> {
+    /// The word is in the list of most popular words, at this rank.
     Ranked(T, u32),
+    /// The word isn't in the ranked list, but it has a score between 0 and 1 from a frequency
+    /// list.
+    Scored(T, f64),
+    /// We know nothing about the word.
     Unranked(T),
 }
 
@@ -16,14 +28,37 @@ impl<T> Ranked<T> {
     pub fn rank(&self) -> Option<u32> {
         match self {
             Self::Ranked(_, rank) => Some(*rank),
-            Self::Unranked(_) => None,
+            Self::Scored(_, _) | Self::Unranked(_) => None,
+        }
+    }
+
+    pub fn score(&self) -> Option<f64> {
+        match self {
+            Self::Scored(_, score) => Some(*score),
+            Self::Ranked(_, _) | Self::Unranked(_) => None,
         }
     }
 
     pub fn is_ranked(&self) -> bool {
         match self {
             Self::Ranked(_, _) => true,
-            Self::Unranked(_) => false,
+            Self::Scored(_, _) | Self::Unranked(_) => false,
+        }
+    }
+
+    /// Returns the word, without what we know about it.
+    pub fn into_inner(self) -> T {
+        match self {
+            Self::Ranked(t, _) | Self::Scored(t, _) | Self::Unranked(t) => t,
+        }
+    }
+
+    /// 0 for ranked words, 1 for scored words, and 2 for unranked words.
+    fn tier(&self) -> u8 {
+        match self {
+            Self::Ranked(_, _) => 0,
+            Self::Scored(_, _) => 1,
+            Self::Unranked(_) => 2,
         }
     }
 }
@@ -42,13 +77,14 @@ impl<T> PartialOrd for Ranked<T> {
     }
 }
 
+/// Ranked words come first, nearest the top first; then scored words, highest score first; then
+/// unranked words.
 impl<T> Ord for Ranked<T> {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
             (Self::Ranked(_, a), Self::Ranked(_, b)) => a.cmp(b),
-            (Ranked::Ranked(_, _), Ranked::Unranked(_)) => Ordering::Less,
-            (Ranked::Unranked(_), Ranked::Ranked(_, _)) => Ordering::Greater,
-            (Self::Unranked(_), Self::Unranked(_)) => Ordering::Equal,
+            (Self::Scored(_, a), Self::Scored(_, b)) => b.partial_cmp(a).unwrap_or(Ordering::Equal),
+            _ => self.tier().cmp(&other.tier()),
         }
     }
 }
@@ -57,6 +93,7 @@ impl<'a> AsRef<str> for Ranked<&'a str> {
     fn as_ref(&self) -> &str {
         match self {
             Ranked::Ranked(t, _) => t,
+            Ranked::Scored(t, _) => t,
             Ranked::Unranked(t) => t,
         }
     }
@@ -66,6 +103,7 @@ impl<T: fmt::Display> fmt::Display for Ranked<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Ranked::Ranked(t, _) => write!(f, "{}*", t),
+            Ranked::Scored(t, _) => write!(f, "{}", t),
             Ranked::Unranked(t) => write!(f, "{}", t),
         }
     }
@@ -75,44 +113,125 @@ impl<T: fmt::Display> fmt::Display for Ranked<T> {
 ///
 /// The most popular English words are `you I to the a and that it of me`. After the most popular
 /// 1000, the next five are `acting accept blow strange saved`.
-#[derive(Default)]
+///
+/// The ranked list only covers the most popular words, so it can be supplemented with
+/// [`FrequencyList`]s, such as Wordnet's tag counts or a list of past crossword answers. Each
+/// list has a weight, and together they give every word they mention a score (see [`score`]).
+///
+/// [`score`]: Popularity::score
+#[derive(Default, Serialize, Deserialize)]
 pub struct Popularity {
     words: HashMap<AsciiString, u32>,
+    lists: Vec<(FrequencyList, f64)>,
 }
 
 impl Popularity {
+    /// Adds a frequency list. Its scores are averaged with those of the other lists, in
+    /// proportion to the weights.
+    pub fn add_list(&mut self, list: FrequencyList, weight: f64) {
+        self.lists.push((list, weight));
+    }
+
+    /// Adds the tag counts from Wordnet's semantic concordance, which cover about 22,000
+    /// lemmas.
+    pub fn add_wordnet_counts(&mut self, wordnet: &Wordnet, weight: f64) {
+        self.add_list(FrequencyList::from_counts(wordnet.tag_counts()), weight);
+    }
+
+    /// Returns a score between 0 and 1 for how popular a word is, where 0 means we know nothing
+    /// about it.
+    ///
+    /// Words in the ranked list score between 0.5 and 1, falling off with the logarithm of their
+    /// rank. Other words score between 0 and 0.5, according to the weighted average of their
+    /// scores in the frequency lists. A ranked word always beats a word that is merely counted.
+    pub fn score(&self, word: &str) -> f64 {
+        match self.to_ranked(word) {
+            Ranked::Ranked(_, rank) => {
+                let last = self.words.len() as f64 + 1.0;
+                0.5 + 0.5 * (1.0 - f64::from(rank).ln() / last.ln())
+            }
+            Ranked::Scored(_, score) => 0.5 * score,
+            Ranked::Unranked(_) => 0.0,
+        }
+    }
+
+    /// The weighted average of a word's scores in the frequency lists.
+    fn list_score(&self, letters: &AsciiString) -> f64 {
+        let total_weight: f64 = self.lists.iter().map(|(_, weight)| weight).sum();
+        if total_weight <= 0.0 {
+            return 0.0;
+        }
+        let weighted: f64 = self
+            .lists
+            .iter()
+            .map(|(list, weight)| list.score(letters) * weight)
+            .sum();
+        weighted / total_weight
+    }
+
     pub fn is_ranked<'a>(&'a self, word: &'a str) -> bool {
         self.words.contains_key(&parse_word(word).0)
     }
 
-    pub fn to_ranked<'a>(&'a self, word: &'a str) -> Ranked<&'a str> {
-        match self.words.get(&parse_word(word).0) {
+    pub fn to_ranked<'a>(&self, word: &'a str) -> Ranked<&'a str> {
+        let letters = parse_word(word).0;
+        match self.words.get(&letters) {
             Some(&rank) => Ranked::Ranked(word, rank),
-            None => Ranked::Unranked(word),
+            None => match self.list_score(&letters) {
+                score if score > 0.0 => Ranked::Scored(word, score),
+                _ => Ranked::Unranked(word),
+            },
         }
     }
 
     /// Compare strings according to this ranking.
     /// * If both are ranked, nearer the top means smaller.
     /// * If only one is ranked, that's the smaller one.
-    /// * If both are unranked, sort them equally.
+    /// * If neither is ranked, the one with the higher score is smaller.
+    /// * If we know nothing about either, sort them equally.
     pub fn cmp(&self, a: &AsciiString, b: &AsciiString) -> Ordering {
-        match (self.words.get(a), self.words.get(b)) {
-            (Some(apos), Some(bpos)) => apos.cmp(bpos),
-            (Some(_), None) => Ordering::Less,
-            (None, Some(_)) => Ordering::Greater,
-            (None, None) => Ordering::Equal,
-        }
+        self.to_ranked(a.to_str()).cmp(&self.to_ranked(b.to_str()))
     }
 
-    /// Sort strings by this ranking, most popular first. Unranked words
-    /// remain in their relative positions at the end of the list.
+    /// Sort strings by this ranking, most popular first. Words we know nothing about remain in
+    /// their relative positions at the end of the list.
     pub fn sort(&self, array: &mut [&str]) {
-        array.sort_by(|a, b| {
-            let akey = parse_word(a).0;
-            let bkey = parse_word(b).0;
-            self.cmp(&akey, &bkey)
-        })
+        let mut ranked: Vec<Ranked<&str>> = array.iter().map(|w| self.to_ranked(w)).collect();
+        ranked.sort();
+        for (slot, word) in array.iter_mut().zip(ranked) {
+            *slot = word.into_inner();
+        }
+    }
+
+    /// Returns the `k` most popular words, most popular first, along with how many words there
+    /// were in all.
+    ///
+    /// This gives the same words as [`sort`](Self::sort) followed by keeping the first `k`, but
+    /// it only holds `k` words at a time, in a bounded heap, so it doesn't need to collect or
+    /// sort the rest.
+    pub fn top<'a, I>(&self, words: I, k: usize) -> (Vec<Ranked<&'a str>>, usize)
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        // The heap's greatest element is the least popular, so that's the one we pop when the
+        // heap is full. The count breaks ties in favour of earlier words, like a stable sort.
+        // A page deep into full results can ask for a large `k`, so the heap only grows as
+        // words arrive.
+        let mut heap = BinaryHeap::new();
+        let mut count = 0;
+        for word in words {
+            heap.push((self.to_ranked(word), count));
+            count += 1;
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+        let top = heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(ranked, _)| ranked)
+            .collect();
+        (top, count)
     }
 }
 
@@ -123,7 +242,10 @@ where
     fn from(iter: I) -> Self {
         let words: HashMap<AsciiString, u32> =
             iter.map(|s| parse_word(s).0).unique().zip(1..).collect();
-        Self { words }
+        Self {
+            words,
+            ..Self::default()
+        }
     }
 }
 
@@ -178,6 +300,57 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn sort_with_counts() {
+        let mut popularity = Popularity::from(vec!["one", "two"].into_iter());
+        let counts = vec![("three", 5), ("four", 20), ("three", 30), ("one", 1)];
+        popularity.add_list(FrequencyList::from_counts(counts), 1.0);
+        let mut list = vec!["five", "four", "three", "two", "one"];
+        popularity.sort(&mut list);
+        assert_eq!(list, vec!["one", "two", "three", "four", "five"]);
+
+        assert!(popularity.score("two") > 0.5);
+        assert!(popularity.score("three") <= 0.5);
+        assert!(popularity.score("four") > 0.0);
+        assert_eq!(popularity.score("five"), 0.0);
+    }
+
+    #[test]
+    pub fn weighted_lists() {
+        let mut popularity = Popularity::default();
+        let english = FrequencyList::parse("the\t1000\nerne\t1\nemu\t30\n").unwrap();
+        let crosswords = FrequencyList::parse("erne\t50\nemu\t10\n").unwrap();
+        popularity.add_list(english, 1.0);
+        popularity.add_list(crosswords, 3.0);
+
+        assert!(popularity.score("erne") > popularity.score("emu"));
+        assert_eq!(popularity.to_ranked("the").rank(), None);
+        assert!(popularity.to_ranked("the").score().unwrap() > 0.0);
+        assert_eq!(popularity.to_ranked("moa"), Ranked::Unranked("moa"));
+
+        let mut list = vec!["moa", "emu", "the", "erne"];
+        popularity.sort(&mut list);
+        assert_eq!(list, vec!["erne", "emu", "the", "moa"]);
+    }
+
+    #[test]
+    pub fn top_matches_sort() {
+        let mut popularity = Popularity::from(vec!["one", "two", "three"].into_iter());
+        popularity.add_list(
+            FrequencyList::from_counts(vec![("six", 5), ("five", 20)]),
+            1.0,
+        );
+        let words = vec!["bleep", "six", "three", "bloop", "five", "one", "blap"];
+        let mut sorted = words.clone();
+        popularity.sort(&mut sorted);
+        for k in 0..=words.len() + 1 {
+            let (top, count) = popularity.top(words.iter().copied(), k);
+            assert_eq!(count, words.len());
+            let top: Vec<_> = top.into_iter().map(Ranked::into_inner).collect();
+            assert_eq!(top, sorted.iter().copied().take(k).collect::<Vec<_>>());
+        }
+    }
+
     #[test]
     pub fn test_rank_with_repeats() {
         let ranked = vec!["three", "one", "four", "one", "five"];
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::{parse_word, AsciiString};
+
+#[derive(Debug, Error)]
+pub enum FrequencyError {
+    #[error("Could not read {path:?}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Line {line}: {message}")]
+    Parse { line: usize, message: String },
+}
+
+/// How often words occur in some corpus, such as a general English frequency list or a count
+/// of past crossword answers.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FrequencyList {
+    counts: HashMap<AsciiString, u64>,
+    max_count: u64,
+}
+
+impl FrequencyList {
+    /// Builds a list from `(word, count)` pairs. A word can appear more than once, for example
+    /// once for each of its senses, and the counts are added together.
+    pub fn from_counts<S, I>(counts: I) -> Self
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = (S, u64)>,
+    {
+        let mut list = Self::default();
+        for (word, count) in counts {
+            let total = list.counts.entry(parse_word(word.as_ref()).0).or_default();
+            *total += count;
+            list.max_count = list.max_count.max(*total);
+        }
+        list
+    }
+
+    /// Reads a frequency file with one word per line, followed by a tab or spaces and its
+    /// count, as in `the<TAB>23135851162`. Blank lines and lines starting with `#` are ignored.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, FrequencyError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|source| FrequencyError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::parse(&text)
+    }
+
+    /// Parses the contents of a frequency file. See [`open`](Self::open) for the format.
+    pub fn parse(text: &str) -> Result<Self, FrequencyError> {
+        let mut counts = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parse_error = |message: &str| FrequencyError::Parse {
+                line: i + 1,
+                message: message.to_string(),
+            };
+            let pos = line
+                .rfind(char::is_whitespace)
+                .ok_or_else(|| parse_error("Expected a word and a count"))?;
+            let count = line[pos + 1..]
+                .parse::<u64>()
+                .map_err(|_| parse_error("Unparseable count"))?;
+            counts.push((line[..pos].trim(), count));
+        }
+        Ok(Self::from_counts(counts))
+    }
+
+    /// Returns a score between 0 and 1 for how often a word occurs, on a log scale, so that the
+    /// most common word scores 1 and a word that isn't in the list scores 0.
+    pub fn score(&self, letters: &AsciiString) -> f64 {
+        match self.counts.get(letters) {
+            Some(&count) => (count as f64).ln_1p() / (self.max_count as f64).ln_1p(),
+            None => 0.0,
+        }
+    }
+
+    /// Returns the number of distinct words in the list
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns `true` if the list is empty
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn parse_frequency_file() {
+        let text = "# word\tcount\nthe\t1000\nof 100\n\nice cream\t10\nThe\t1000\n";
+        let list = FrequencyList::parse(text).unwrap();
+        assert_eq!(list.len(), 3);
+        let score = |word| list.score(&parse_word(word).0);
+        assert_eq!(score("the"), 1.0);
+        assert!(score("of") > score("ice-cream"));
+        assert!(score("ice-cream") > 0.0);
+        assert_eq!(score("zebra"), 0.0);
+    }
+
+    #[test]
+    pub fn parse_frequency_errors() {
+        match FrequencyList::parse("the\t1000\nof\n") {
+            Err(FrequencyError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+        assert!(FrequencyList::parse("the many\n").is_err());
+    }
+}
@@ -0,0 +1,263 @@
+use deunicode::deunicode;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::wordnet::{stem, WordType};
+use crate::{phrase_key, Dictionary};
+
+/// BM25 term frequency saturation.
+const K1: f64 = 1.2;
+/// BM25 length normalization.
+const B: f64 = 0.75;
+
+/// Words too common to say anything about a definition.
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "any", "are", "as", "at", "be", "by", "for", "from", "in", "into", "is",
+    "it", "its", "of", "on", "or", "that", "the", "this", "to", "which", "who", "with",
+];
+
+type DocId = usize;
+
+/// Finds words from their definitions.
+///
+/// Every definition in a [`Dictionary`] is split into words, which are reduced to their base
+/// forms with Wordnet's stemmer, and added to an inverted index. A search ranks each definition
+/// against the query using [BM25], so that rare words in the query count for more than common
+/// ones.
+///
+/// [BM25]: https://en.wikipedia.org/wiki/Okapi_BM25
+#[derive(Default, Serialize, Deserialize)]
+pub struct ReverseDictionary {
+    docs: Vec<Doc>,
+    postings: HashMap<String, Vec<(DocId, u32)>>,
+    vocabulary: HashSet<String>,
+    /// The names of dictionary sources, indexed by `Doc::source`.
+    sources: Vec<String>,
+    avg_len: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Doc {
+    word: String,
+    word_type: Option<WordType>,
+    definition: String,
+    source: usize,
+    len: u32,
+}
+
+/// A word found by [`ReverseDictionary::search`].
+#[derive(Debug, PartialEq)]
+pub struct ReverseMatch<'a> {
+    /// The word or phrase, as the dictionary spells it.
+    pub word: &'a str,
+    pub word_type: Option<WordType>,
+    /// The definition that best matched the query.
+    pub definition: &'a str,
+    /// The dictionary source the definition came from.
+    pub source: &'a str,
+    pub score: f64,
+}
+
+impl ReverseDictionary {
+    /// Builds the index from every definition in the dictionary.
+    pub fn new(dictionary: &Dictionary) -> Self {
+        let mut index = Self {
+            vocabulary: dictionary
+                .definitions()
+                .map(|definition| phrase_key(definition.headword))
+                .collect(),
+            ..Self::default()
+        };
+
+        let mut total_len = 0;
+        for definition in dictionary.definitions() {
+            let doc_id = index.docs.len();
+            let source = match index.sources.iter().position(|s| s == definition.source) {
+                Some(source) => source,
+                None => {
+                    index.sources.push(definition.source.to_string());
+                    index.sources.len() - 1
+                }
+            };
+            let mut counts: HashMap<String, u32> = HashMap::new();
+            let mut len = 0;
+            for term in index.terms(gloss(definition.text)) {
+                *counts.entry(term).or_default() += 1;
+                len += 1;
+            }
+            for (term, count) in counts {
+                index
+                    .postings
+                    .entry(term)
+                    .or_default()
+                    .push((doc_id, count));
+            }
+            total_len += len;
+            index.docs.push(Doc {
+                word: definition.headword.to_string(),
+                word_type: definition.word_type,
+                definition: definition.text.to_string(),
+                source,
+                len,
+            });
+        }
+        index.avg_len = total_len as f64 / index.docs.len().max(1) as f64;
+        index
+    }
+
+    /// Checks that every posting and definition points into the index, as they always do unless
+    /// a saved ReverseDictionary has been corrupted.
+    pub(crate) fn is_consistent(&self) -> bool {
+        self.postings
+            .values()
+            .flatten()
+            .all(|&(doc_id, _)| doc_id < self.docs.len())
+            && self.docs.iter().all(|doc| doc.source < self.sources.len())
+    }
+
+    /// Returns words whose definitions match the query, best match first. Each word appears
+    /// once, with the definition that matched best.
+    pub fn search(&self, query: &str) -> Vec<ReverseMatch<'_>> {
+        let mut terms = self.terms(query);
+        terms.sort_unstable();
+        terms.dedup();
+
+        let num_docs = self.docs.len() as f64;
+        let mut scores: HashMap<DocId, f64> = HashMap::new();
+        for term in &terms {
+            if let Some(postings) = self.postings.get(term) {
+                let df = postings.len() as f64;
+                let idf = ((num_docs - df + 0.5) / (df + 0.5) + 1.0).ln();
+                for &(doc_id, tf) in postings {
+                    let tf = f64::from(tf);
+                    let len = f64::from(self.docs[doc_id].len);
+                    let norm = K1 * (1.0 - B + B * len / self.avg_len);
+                    *scores.entry(doc_id).or_default() += idf * tf * (K1 + 1.0) / (tf + norm);
+                }
+            }
+        }
+
+        let mut best: HashMap<&str, (DocId, f64)> = HashMap::new();
+        for (doc_id, score) in scores {
+            let word = self.docs[doc_id].word.as_str();
+            let entry = best.entry(word).or_insert((doc_id, score));
+            if score > entry.1 {
+                *entry = (doc_id, score);
+            }
+        }
+
+        let mut matches = best
+            .into_iter()
+            .map(|(word, (doc_id, score))| ReverseMatch {
+                word,
+                word_type: self.docs[doc_id].word_type,
+                definition: &self.docs[doc_id].definition,
+                source: &self.sources[self.docs[doc_id].source],
+                score,
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap()
+                .then_with(|| a.word.cmp(b.word))
+        });
+        matches
+    }
+
+    /// Splits text into index terms: lower case, without stop words, and stemmed.
+    fn terms(&self, text: &str) -> Vec<String> {
+        deunicode(text)
+            .to_lowercase()
+            .split(|ch: char| !ch.is_ascii_alphabetic())
+            .filter(|token| !token.is_empty() && !STOP_WORDS.contains(token))
+            .map(|token| self.base_form(token))
+            .collect()
+    }
+
+    /// Reduces a word to the first of its stemmed forms that is in the dictionary, so that
+    /// "barking" and "barks" both become "bark". Words that aren't in the dictionary are cut
+    /// down to their shortest stemmed form, which is at least consistent between inflections.
+    fn base_form(&self, token: &str) -> String {
+        if self.vocabulary.contains(token) {
+            return token.to_string();
+        }
+        let base_forms = stem(token)
+            .map(|stemmed| stemmed.base_form)
+            .collect::<Vec<_>>();
+        base_forms
+            .iter()
+            .find(|base_form| self.vocabulary.contains(*base_form))
+            .or_else(|| base_forms.iter().min_by_key(|base_form| base_form.len()))
+            .cloned()
+            .unwrap_or_else(|| token.to_string())
+    }
+}
+
+/// The part of a Wordnet definition before any example sentences, which tend to repeat the
+/// word being defined.
+fn gloss(definition: &str) -> &str {
+    match definition.find("; \"") {
+        Some(pos) => &definition[..pos],
+        None => definition,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example_dictionary() -> Dictionary {
+        let mut dict = Dictionary::new();
+        dict.insert("dog", "a domesticated animal that barks", WordType::Noun);
+        dict.insert(
+            "bark",
+            "make a loud abrupt noise like a dog",
+            WordType::Verb,
+        );
+        dict.insert(
+            "cat",
+            "a small domesticated animal that purrs",
+            WordType::Noun,
+        );
+        dict.insert(
+            "alarm_clock",
+            "a clock that makes a loud noise to wake you; \"the alarm clock rang\"",
+            WordType::Noun,
+        );
+        dict
+    }
+
+    fn words(matches: Vec<ReverseMatch<'_>>) -> Vec<&str> {
+        matches.into_iter().map(|m| m.word).collect()
+    }
+
+    #[test]
+    pub fn search_ranks_rare_terms_higher() {
+        let rev = ReverseDictionary::new(&example_dictionary());
+        assert_eq!(words(rev.search("animal that barks")), vec!["dog", "cat"]);
+    }
+
+    #[test]
+    pub fn search_stems_terms() {
+        let rev = ReverseDictionary::new(&example_dictionary());
+        assert_eq!(words(rev.search("barking"))[0], "dog");
+        assert_eq!(words(rev.search("purring animals"))[0], "cat");
+    }
+
+    #[test]
+    pub fn search_returns_phrases_and_definitions() {
+        let rev = ReverseDictionary::new(&example_dictionary());
+        let matches = rev.search("wake up");
+        assert_eq!(matches[0].word, "alarm clock");
+        assert_eq!(matches[0].word_type, Some(WordType::Noun));
+        assert!(matches[0].definition.starts_with("a clock that makes"));
+    }
+
+    #[test]
+    pub fn search_ignores_examples_and_stop_words() {
+        let rev = ReverseDictionary::new(&example_dictionary());
+        assert!(rev.search("rang").is_empty());
+        assert!(rev.search("the of a").is_empty());
+    }
+}
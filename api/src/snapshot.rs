@@ -0,0 +1,458 @@
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::hash::Hasher;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::thesaurus::THESAURUS_TEXT;
+use crate::{Dictionary, Lexicon, Popularity, ReverseDictionary, Thesaurus, Wordnet};
+
+/// The first bytes of every index file.
+const MAGIC: &[u8; 8] = b"WORDFUN\0";
+
+/// The format of the data after the header. Bump this whenever a stored type changes shape,
+/// so that old index files are rebuilt instead of misread.
+pub const SNAPSHOT_VERSION: u32 = 5;
+
+const HEADER_LEN: usize = MAGIC.len() + 4;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("Could not read {path:?}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Could not write {path:?}")]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Not a wordfun index")]
+    NotAnIndex,
+    #[error("Index is version {found}, but this build reads version {expected}")]
+    WrongVersion { found: u32, expected: u32 },
+    #[error("The {file} has changed since the index was built from {}", display_path(.built_from))]
+    OtherSources {
+        file: &'static str,
+        built_from: Option<PathBuf>,
+    },
+    #[error("Could not decode index")]
+    Decode(#[from] bincode::Error),
+    #[error("The lexicon in the index is corrupt")]
+    CorruptLexicon,
+    #[error("The thesaurus in the index is corrupt")]
+    CorruptThesaurus,
+    #[error("The reverse dictionary in the index is corrupt")]
+    CorruptReverseDictionary,
+}
+
+/// The reference data that takes longest to build, saved to a binary file so that the server
+/// can start without parsing text files.
+///
+/// A snapshot holds what's built from the data directory and the bundled Wordnet and
+/// thesaurus files: the tagged [`Lexicon`], the Wordnet [`Dictionary`] and its
+/// [`ReverseDictionary`], the [`Thesaurus`], and the [`Popularity`] of the popular words list
+/// and Wordnet's tag counts. Glossaries, frequency lists and past answers change from run to
+/// run, so they are added after loading.
+///
+/// The file starts with `WORDFUN\0` and a little-endian `u32` version, followed by the data in
+/// [bincode]. The [`DataSources`] come first, so that
+/// [`open_built_from`](Self::open_built_from) can check them before decoding the rest. The
+/// whole file is read into memory and decoded: the lexicon's buffers decode with a copy each,
+/// so there's little to gain from mapping the file instead.
+///
+/// [bincode]: https://docs.rs/bincode/1
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub sources: DataSources,
+    pub lexicon: Lexicon,
+    pub dictionary: Dictionary,
+    pub reverse_dictionary: ReverseDictionary,
+    pub thesaurus: Thesaurus,
+    pub popularity: Popularity,
+}
+
+impl Snapshot {
+    /// Builds the reference data from the lexicon and popular words lists, a thesaurus file
+    /// (or the bundled one, if it's `None`), and Wordnet, which were read from `sources`.
+    pub fn build(
+        lexicon: &str,
+        popular_words: &str,
+        thesaurus: Option<&str>,
+        wordnet: &Wordnet,
+        sources: DataSources,
+    ) -> Self {
+        let mut lexicon = Lexicon::new(lexicon.lines());
+        lexicon.tag_from_wordnet(wordnet);
+        let mut popularity = Popularity::from(popular_words.lines());
+        popularity.add_wordnet_counts(wordnet, 1.0);
+        let thesaurus = match thesaurus {
+            Some(text) => Thesaurus::load(text, wordnet),
+            None => Thesaurus::init(wordnet),
+        };
+        let dictionary = Dictionary::from_wordnet(wordnet);
+        Self {
+            sources,
+            lexicon,
+            reverse_dictionary: ReverseDictionary::new(&dictionary),
+            dictionary,
+            thesaurus,
+            popularity,
+        }
+    }
+
+    /// Reads a snapshot saved by [`save`](Self::save).
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        Self::from_bytes(&read(path.as_ref())?)
+    }
+
+    /// Reads a snapshot saved by [`save`](Self::save), checking that it was built from
+    /// `sources`.
+    pub fn open_built_from<P: AsRef<Path>>(
+        path: P,
+        sources: &DataSources,
+    ) -> Result<Self, SnapshotError> {
+        Self::from_bytes_built_from(&read(path.as_ref())?, sources)
+    }
+
+    /// Writes the snapshot to `path`. It's written to a temporary file in the same directory
+    /// first, and renamed into place, so that an interrupted save never leaves a truncated
+    /// index where the server will look for it.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        let path = path.as_ref();
+        let bytes = self.to_bytes()?;
+        let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(format!(".tmp-{}", std::process::id()));
+        let tmp_path = path.with_file_name(tmp_name);
+        let write = || -> io::Result<()> {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(&bytes)?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, path)
+        };
+        write().map_err(|source| {
+            let _ = fs::remove_file(&tmp_path);
+            SnapshotError::Write {
+                path: path.to_path_buf(),
+                source,
+            }
+        })
+    }
+
+    /// Decodes a snapshot, checking that it's an index file of the current version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        Self::decode(Self::data(bytes)?)
+    }
+
+    /// Decodes a snapshot like [`from_bytes`](Self::from_bytes), and also checks that it was
+    /// built from `sources`.
+    pub fn from_bytes_built_from(
+        bytes: &[u8],
+        sources: &DataSources,
+    ) -> Result<Self, SnapshotError> {
+        let data = Self::data(bytes)?;
+        let found: DataSources = bincode::DefaultOptions::new()
+            .allow_trailing_bytes()
+            .deserialize(data)?;
+        if let Some((file, built_from)) = found.changed_from(sources) {
+            return Err(SnapshotError::OtherSources {
+                file,
+                built_from: built_from.path.clone(),
+            });
+        }
+        Self::decode(data)
+    }
+
+    /// Checks the header, and returns the data after it.
+    fn data(bytes: &[u8]) -> Result<&[u8], SnapshotError> {
+        if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(SnapshotError::NotAnIndex);
+        }
+        let mut version = [0; 4];
+        version.copy_from_slice(&bytes[MAGIC.len()..HEADER_LEN]);
+        let version = u32::from_le_bytes(version);
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::WrongVersion {
+                found: version,
+                expected: SNAPSHOT_VERSION,
+            });
+        }
+        Ok(&bytes[HEADER_LEN..])
+    }
+
+    fn decode(data: &[u8]) -> Result<Self, SnapshotError> {
+        let mut snapshot: Self = bincode::DefaultOptions::new().deserialize(data)?;
+        if !snapshot.lexicon.is_consistent() {
+            return Err(SnapshotError::CorruptLexicon);
+        }
+        if !snapshot.thesaurus.is_consistent() {
+            return Err(SnapshotError::CorruptThesaurus);
+        }
+        if !snapshot.reverse_dictionary.is_consistent() {
+            return Err(SnapshotError::CorruptReverseDictionary);
+        }
+        // Decoding grows the buffers a bit at a time, leaving them with spare capacity.
+        snapshot.lexicon.shrink_to_fit();
+        Ok(snapshot)
+    }
+
+    /// Encodes the snapshot with its header, as saved by [`save`](Self::save).
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        bincode::DefaultOptions::new().serialize_into(&mut bytes, self)?;
+        Ok(bytes)
+    }
+}
+
+fn display_path(path: &Option<PathBuf>) -> String {
+    match path {
+        Some(path) => format!("{:?}", path),
+        None => "the bundled copy".to_string(),
+    }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, SnapshotError> {
+    fs::read(path).map_err(|source| SnapshotError::Read {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Where the data in a [`Snapshot`] came from. An index built from files with other contents
+/// is rebuilt instead of used.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataSources {
+    pub lexicon: DataSource,
+    pub popular_words: DataSource,
+    pub thesaurus: DataSource,
+    pub wordnet: DataSource,
+}
+
+impl DataSources {
+    /// Finds the first data source in `self` whose contents differ from the one in `current`,
+    /// and names it. Only the hashes are compared, so the same files reached by another path,
+    /// such as `./data` instead of `data`, still match.
+    fn changed_from(&self, current: &Self) -> Option<(&'static str, &DataSource)> {
+        let pairs = [
+            ("lexicon", &self.lexicon, &current.lexicon),
+            (
+                "popular words list",
+                &self.popular_words,
+                &current.popular_words,
+            ),
+            ("thesaurus", &self.thesaurus, &current.thesaurus),
+            ("Wordnet", &self.wordnet, &current.wordnet),
+        ];
+        pairs
+            .iter()
+            .find(|(_, built_from, current)| built_from.hash != current.hash)
+            .map(|&(file, built_from, _)| (file, built_from))
+    }
+}
+
+/// A data file, or a copy of some data built into the binary, and a hash of its contents.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataSource {
+    /// `None` means the bundled copy. This is only for messages: a source is identified by its
+    /// hash.
+    pub path: Option<PathBuf>,
+    pub hash: u64,
+}
+
+impl DataSource {
+    pub fn new(path: Option<PathBuf>, contents: &str) -> Self {
+        let mut hasher = ContentHasher::default();
+        hasher.write(contents.as_bytes());
+        Self {
+            path,
+            hash: hasher.finish(),
+        }
+    }
+
+    /// The thesaurus built into the binary, which [`Thesaurus::init`] loads.
+    pub fn bundled_thesaurus() -> Self {
+        Self::new(None, THESAURUS_TEXT)
+    }
+
+    /// Wordnet, read from the `dict` directory at `path`, or the bundled copy if it's `None`.
+    pub fn wordnet(path: Option<PathBuf>, wordnet: &Wordnet) -> Self {
+        let mut hasher = ContentHasher::default();
+        wordnet.hash_files(&mut hasher);
+        Self {
+            path,
+            hash: hasher.finish(),
+        }
+    }
+}
+
+/// 64-bit FNV-1a. Unlike the standard library's hasher, it's guaranteed to give the same
+/// hashes in every build, so they can be saved.
+pub(crate) struct ContentHasher(u64);
+
+impl Default for ContentHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for ContentHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Tag, WordType};
+
+    fn small_snapshot() -> Snapshot {
+        let mut lexicon = Lexicon::new(vec!["Aachen", "bee's knees", "café"].into_iter());
+        lexicon.tag(vec![("aachen", Tag::Location)]);
+        let mut dictionary = Dictionary::new();
+        dictionary.insert("cafe", "a small restaurant", WordType::Noun);
+        Snapshot {
+            sources: sources("Aachen\nbee's knees\ncafé\n"),
+            lexicon,
+            reverse_dictionary: ReverseDictionary::new(&dictionary),
+            dictionary,
+            thesaurus: Thesaurus::import(vec!["cafe,bistro"].into_iter()),
+            popularity: Popularity::from(vec!["the", "cafe"].into_iter()),
+        }
+    }
+
+    fn sources(lexicon: &str) -> DataSources {
+        DataSources {
+            lexicon: DataSource::new(Some(PathBuf::from("lexicon.txt")), lexicon),
+            popular_words: DataSource::new(Some(PathBuf::from("popular.txt")), "the\ncafe\n"),
+            thesaurus: DataSource::new(None, "cafe,bistro\n"),
+            wordnet: DataSource::new(None, ""),
+        }
+    }
+
+    #[test]
+    pub fn round_trip() {
+        let bytes = small_snapshot().to_bytes().unwrap();
+        assert!(bytes.starts_with(MAGIC));
+        let snapshot = Snapshot::from_bytes(&bytes).unwrap();
+
+        let entries: Vec<_> = snapshot.lexicon.entries().collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].word(), "bee's knees");
+        assert_eq!(entries[1], crate::Entry::from("bee's knees"));
+        assert!(entries[0].tags().contains(Tag::Location));
+        let results = snapshot.lexicon.anagram("feac").unwrap();
+        assert_eq!(results.collect::<Vec<_>>(), vec!["café"]);
+        assert_eq!(snapshot.dictionary.len(), 1);
+        assert_eq!(
+            snapshot.reverse_dictionary.search("restaurant")[0].word,
+            "cafe"
+        );
+        assert!(snapshot.popularity.is_ranked("café"));
+    }
+
+    #[test]
+    pub fn save_and_open() {
+        let dir = std::env::temp_dir().join(format!("wordfun-save-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("wordfun.index");
+        fs::write(&path, "an old index").unwrap();
+        small_snapshot().save(&path).unwrap();
+        assert_eq!(Snapshot::open(&path).unwrap().lexicon.len(), 3);
+        // Only the index is left: the temporary file has been renamed over it.
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    pub fn reject_other_files() {
+        let mut bytes = small_snapshot().to_bytes().unwrap();
+        assert!(matches!(
+            Snapshot::from_bytes(b"aardvark\n"),
+            Err(SnapshotError::NotAnIndex)
+        ));
+
+        bytes[MAGIC.len()] += 1;
+        assert!(matches!(
+            Snapshot::from_bytes(&bytes),
+            Err(SnapshotError::WrongVersion { found, expected })
+                if found == SNAPSHOT_VERSION + 1 && expected == SNAPSHOT_VERSION
+        ));
+
+        bytes[MAGIC.len()] -= 1;
+        bytes.truncate(bytes.len() - 1);
+        assert!(matches!(
+            Snapshot::from_bytes(&bytes),
+            Err(SnapshotError::Decode(_))
+        ));
+    }
+
+    #[test]
+    pub fn reject_corrupt_thesaurus() {
+        // A struct encodes as its fields one after the other, so the snapshot can be put
+        // together from a thesaurus encoded on its own.
+        let snapshot = small_snapshot();
+        let options = bincode::DefaultOptions::new();
+        let mut thesaurus = options.serialize(&snapshot.thesaurus).unwrap();
+        // One entry, of two words, with ids 0 and 1.
+        assert_eq!(thesaurus[..4], [1, 2, 0, 1]);
+        thesaurus[3] = 200;
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        bytes.extend(options.serialize(&snapshot.sources).unwrap());
+        bytes.extend(options.serialize(&snapshot.lexicon).unwrap());
+        bytes.extend(options.serialize(&snapshot.dictionary).unwrap());
+        bytes.extend(options.serialize(&snapshot.reverse_dictionary).unwrap());
+        bytes.extend(thesaurus);
+        bytes.extend(options.serialize(&snapshot.popularity).unwrap());
+        assert!(matches!(
+            Snapshot::from_bytes(&bytes),
+            Err(SnapshotError::CorruptThesaurus)
+        ));
+    }
+
+    #[test]
+    pub fn reject_other_sources() {
+        let bytes = small_snapshot().to_bytes().unwrap();
+        let built_from = sources("Aachen\nbee's knees\ncafé\n");
+        let snapshot = Snapshot::from_bytes_built_from(&bytes, &built_from).unwrap();
+        assert_eq!(snapshot.sources, built_from);
+
+        // The same contents by another path are the same source.
+        let mut moved = built_from.clone();
+        moved.lexicon.path = Some(PathBuf::from("./words.txt"));
+        assert!(Snapshot::from_bytes_built_from(&bytes, &moved).is_ok());
+
+        let error = Snapshot::from_bytes_built_from(&bytes, &sources("Aachen\nbee's knees\n"))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "The lexicon has changed since the index was built from \"lexicon.txt\""
+        );
+    }
+
+    #[test]
+    pub fn hash_contents() {
+        // Test vectors from the FNV reference implementation.
+        assert_eq!(DataSource::new(None, "").hash, 0xcbf2_9ce4_8422_2325);
+        assert_eq!(DataSource::new(None, "a").hash, 0xaf63_dc4c_8601_ec8c);
+        assert_ne!(
+            DataSource::new(None, "cafe").hash,
+            DataSource::new(None, "café").hash
+        );
+    }
+}
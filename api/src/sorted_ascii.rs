@@ -1,7 +1,11 @@
 use std::cmp::Ordering;
 
-#[derive(Debug, PartialEq, Default, Clone)]
-pub struct SortedAscii(Vec<u8>);
+/// Letters in alphabetical order, including repeats.
+///
+/// Like [`AsciiString`](crate::AsciiString), this normally owns its letters, but a
+/// `SortedAscii<&[u8]>` borrows them from a [`Lexicon`](crate::Lexicon).
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+pub struct SortedAscii<B = Vec<u8>>(B);
 
 impl SortedAscii {
     pub fn from_bytes(bytes: &[u8]) -> Self {
@@ -9,21 +13,31 @@ impl SortedAscii {
         vec.sort_unstable();
         Self(vec)
     }
+}
+
+impl<'a> SortedAscii<&'a [u8]> {
+    /// Borrows letters that are already sorted.
+    pub(crate) fn borrowed(sorted: &'a [u8]) -> Self {
+        Self(sorted)
+    }
+}
 
+impl<B: AsRef<[u8]>> SortedAscii<B> {
     pub fn to_str(&self) -> &str {
         // OK to unwrap this because the bytes are all ASCII.
-        std::str::from_utf8(&self.0).unwrap()
+        std::str::from_utf8(self.0.as_ref()).unwrap()
     }
 
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.0.as_ref().len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.0.as_ref().is_empty()
     }
 
-    pub fn is_subset(&self, other: &SortedAscii) -> bool {
+    pub fn is_subset<C: AsRef<[u8]>>(&self, other: &SortedAscii<C>) -> bool {
+        let (ours, theirs) = (self.0.as_ref(), other.0.as_ref());
         let mut i = 0;
         let mut j = 0;
         loop {
@@ -35,8 +49,8 @@ impl SortedAscii {
                 // Exhausted other while self still has elements
                 return false;
             }
-            let a = self.0[i];
-            let b = other.0[j];
+            let a = ours[i];
+            let b = theirs[j];
 
             match a.cmp(&b) {
                 Ordering::Equal => {
@@ -55,8 +69,8 @@ impl SortedAscii {
     }
 }
 
-impl AsRef<[u8]> for SortedAscii {
+impl<B: AsRef<[u8]>> AsRef<[u8]> for SortedAscii<B> {
     fn as_ref(&self) -> &[u8] {
-        &self.0
+        self.0.as_ref()
     }
 }
@@ -1,30 +1,95 @@
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::fmt::Display;
+use std::str::FromStr;
+use thiserror::Error;
 
-const THESAURUS_TEXT: &str = include_str!("thesaurus");
+use crate::wordnet::{
+    inflect_phrase, stem_phrase, Inflection, Relation, Synset, WordType, Wordnet,
+};
+use crate::{phrase_key, Lexicon};
+
+pub(crate) const THESAURUS_TEXT: &str = include_str!("thesaurus");
 
 type WordId = u32;
+type SynsetId = u32;
+type EntryId = u32;
 
-#[derive(Default, Debug, PartialEq)]
+#[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Thesaurus {
     entries: Vec<Entry>,
+    /// Maps each word to the entries it appears in, in order, so that the thesaurus source
+    /// doesn't have to scan every entry.
+    word_entries: HashMap<WordId, Vec<EntryId>>,
     known_terms: Vec<Word>,
-    words_lookup: HashMap<&'static str, WordId>,
+    words_lookup: HashMap<String, WordId>,
+    /// Maps each folded term (see [`phrase_key`]) to the terms that fold to it, so that
+    /// lookups ignore case, accents and punctuation.
+    keys_lookup: HashMap<String, SmallVec<[WordId; 1]>>,
+    synsets: Vec<SynsetEntry>,
+    word_synsets: HashMap<WordId, Vec<SynsetId>>,
 }
 
-#[derive(Default, Debug, PartialEq)]
+#[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
 struct Entry {
     word_ids: Vec<WordId>,
 }
 
+/// A Wordnet synset, with its pointers resolved to other synsets in the thesaurus.
+#[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
+struct SynsetEntry {
+    word_ids: Vec<WordId>,
+    similar: Vec<SynsetId>,
+    hypernyms: Vec<SynsetId>,
+}
+
+/// Where a synonym came from.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// The bundled thesaurus file.
+    Thesaurus,
+    /// Another member of a Wordnet synset containing the word.
+    Wordnet,
+    /// A member of a synset that Wordnet marks as similar to one containing the word
+    /// (adjectives only).
+    Similar,
+    /// A member of a more general synset, e.g., "canine" for "dog".
+    Hypernym,
+}
+
+impl Source {
+    /// The sources used when the caller doesn't ask for any in particular.
+    pub const DEFAULT: &'static [Source] = &[Source::Thesaurus, Source::Wordnet];
+}
+
+#[derive(Debug, Error)]
+#[error("unknown synonym source {0:?}")]
+pub struct UnknownSource(String);
+
+impl FromStr for Source {
+    type Err = UnknownSource;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "thesaurus" => Ok(Source::Thesaurus),
+            "wordnet" => Ok(Source::Wordnet),
+            "similar" => Ok(Source::Similar),
+            "hypernym" => Ok(Source::Hypernym),
+            _ => Err(UnknownSource(s.to_string())),
+        }
+    }
+}
+
 /// Represents the lengths of the individual words in a thesaurus term.
 /// For example, "bacon" has length "5"; "bacon and eggs" has length
 /// "11 (5,3,4)".
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct WordLengths {
     lengths: SmallVec<[u8; 8]>,
 }
@@ -55,14 +120,73 @@ impl WordLengths {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct Word {
-    pub term: &'static str,
+    pub term: String,
     pub word_lengths: WordLengths,
 }
 
+/// A word returned from a thesaurus lookup, along with the sources that suggested it.
+///
+/// The word is usually borrowed from the thesaurus, but it is owned if it had to be inflected
+/// to match the query.
+#[derive(Debug, PartialEq)]
+pub struct Synonym<'a> {
+    pub word: Cow<'a, Word>,
+    pub sources: SmallVec<[Source; 4]>,
+}
+
+/// A candidate found by [`Thesaurus::expand`], along with how it was found.
+#[derive(Debug, PartialEq)]
+pub struct Expansion<'a> {
+    pub synonym: Synonym<'a>,
+    /// The number of hops from the word that was looked up. Direct synonyms have distance 1.
+    pub distance: usize,
+    /// The number of distinct shortest paths from the word to this candidate.
+    pub paths: u32,
+    /// The words in between the word that was looked up and this candidate, on the first path
+    /// found. This is empty for direct synonyms.
+    pub via: Vec<&'a Word>,
+}
+
+impl<'a> Expansion<'a> {
+    /// How plausible this candidate is. Each extra hop makes a candidate less likely to be a
+    /// real synonym, but several independent paths make it more likely. A candidate two hops
+    /// away with four paths scores the same as a direct synonym.
+    pub fn score(&self) -> f64 {
+        f64::from(self.paths) / (self.distance * self.distance) as f64
+    }
+
+    /// Inflects the synonym to a form that's in the lexicon, if it has one.
+    fn inflect(
+        self,
+        word_type: WordType,
+        inflection: Inflection,
+        lexicon: &Lexicon,
+    ) -> Option<Self> {
+        let term = inflect_phrase(&self.synonym.word.term, word_type, inflection, |word| {
+            lexicon.contains(word)
+        })?;
+        Some(Self {
+            synonym: Synonym {
+                word: Cow::Owned(Word::new(term)),
+                sources: self.synonym.sources,
+            },
+            ..self
+        })
+    }
+}
+
+/// The state of a word during [`Thesaurus::expand`].
+struct Visit {
+    distance: usize,
+    paths: u32,
+    via: Vec<WordId>,
+    sources: SmallVec<[Source; 4]>,
+}
+
 impl Word {
-    fn new(term: &'static str) -> Self {
+    fn new(term: String) -> Self {
         let lengths = term
             .bytes()
             .group_by(|b| b.is_ascii_alphabetic())
@@ -85,7 +209,7 @@ impl Word {
 
 impl Display for Word {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.pad(self.term)
+        f.pad(&self.term)
     }
 }
 
@@ -94,103 +218,409 @@ impl Thesaurus {
         Self::default()
     }
 
-    pub fn init() -> Self {
-        Self::import(THESAURUS_TEXT.lines())
+    /// Loads the bundled thesaurus, augmented with synonyms from Wordnet.
+    pub fn init(wordnet: &Wordnet) -> Self {
+        Self::load(THESAURUS_TEXT, wordnet)
+    }
+
+    /// Loads a thesaurus in the same format as the bundled one, with a comma-separated list of
+    /// synonyms on each line, augmented with synonyms from Wordnet.
+    pub fn load(text: &str, wordnet: &Wordnet) -> Self {
+        let mut thesaurus = Self::import(text.lines());
+        thesaurus.import_wordnet(wordnet.synsets());
+        thesaurus
     }
 
-    pub fn import<I>(lines: I) -> Self
+    pub fn import<'a, I>(lines: I) -> Self
     where
-        I: Iterator<Item = &'static str>,
+        I: Iterator<Item = &'a str>,
     {
-        let mut terms = Vec::new();
-        let mut term_id_map: HashMap<&'static str, WordId> = HashMap::new();
+        let mut thesaurus = Self::new();
+        for line in lines {
+            thesaurus.push(&line.split(',').collect::<Vec<_>>());
+        }
+        thesaurus
+    }
 
-        let mut entries = Vec::new();
+    /// Adds Wordnet synsets as a second source of synonyms. Pointers to synsets that aren't
+    /// in the list are ignored.
+    pub fn import_wordnet<I>(&mut self, synsets: I)
+    where
+        I: IntoIterator<Item = Synset>,
+    {
+        let synsets = synsets.into_iter().collect::<Vec<_>>();
+        let first_id = self.synsets.len() as SynsetId;
+        let synset_ids: HashMap<(WordType, u64), SynsetId> = synsets
+            .iter()
+            .map(|synset| (synset.word_type, synset.offset))
+            .zip(first_id..)
+            .collect();
 
-        for line in lines {
-            let mut word_ids = Vec::new();
-            for term in line.split(',') {
-                let id = if let Some(&id) = term_id_map.get(term) {
-                    id
-                } else {
-                    let id = terms.len() as u32;
-                    terms.push(term);
-                    term_id_map.insert(term, id);
-                    id
-                };
-                word_ids.push(id);
+        for (synset, synset_id) in synsets.iter().zip(first_id..) {
+            let word_ids = synset
+                .lemmas
+                .iter()
+                .map(|lemma| self.intern(&lemma.replace('_', " ")))
+                .unique()
+                .collect::<Vec<_>>();
+            for &word_id in &word_ids {
+                self.word_synsets
+                    .entry(word_id)
+                    .or_default()
+                    .push(synset_id);
             }
 
-            entries.push(Entry { word_ids });
+            let pointers_to = |relation| {
+                synset
+                    .pointers
+                    .iter()
+                    .filter(|pointer| pointer.relation == relation)
+                    .filter_map(|pointer| synset_ids.get(&(pointer.word_type, pointer.offset)))
+                    .cloned()
+                    .collect()
+            };
+
+            self.synsets.push(SynsetEntry {
+                word_ids,
+                similar: pointers_to(Relation::SimilarTo),
+                hypernyms: pointers_to(Relation::Hypernym),
+            });
+        }
+    }
+
+    pub fn push(&mut self, synonyms: &[&str]) {
+        let word_ids: Vec<WordId> = synonyms.iter().map(|term| self.intern(term)).collect();
+        let entry_id = self.entries.len() as EntryId;
+        for &word_id in word_ids.iter().unique() {
+            self.word_entries.entry(word_id).or_default().push(entry_id);
+        }
+        self.entries.push(Entry { word_ids });
+    }
+
+    /// Checks that every word and synset id points into the thesaurus, as they always do
+    /// unless a saved Thesaurus has been corrupted.
+    pub(crate) fn is_consistent(&self) -> bool {
+        let is_word = |&id: &WordId| (id as usize) < self.known_terms.len();
+        let is_synset = |&id: &SynsetId| (id as usize) < self.synsets.len();
+        let is_entry = |&id: &EntryId| (id as usize) < self.entries.len();
+        self.entries
+            .iter()
+            .all(|entry| !entry.word_ids.is_empty() && entry.word_ids.iter().all(is_word))
+            && self
+                .word_entries
+                .iter()
+                .all(|(word_id, entry_ids)| is_word(word_id) && entry_ids.iter().all(is_entry))
+            && self.words_lookup.values().all(is_word)
+            && self.keys_lookup.values().flatten().all(is_word)
+            && self.synsets.iter().all(|synset| {
+                synset.word_ids.iter().all(is_word)
+                    && synset.similar.iter().all(is_synset)
+                    && synset.hypernyms.iter().all(is_synset)
+            })
+            && self
+                .word_synsets
+                .iter()
+                .all(|(word_id, synset_ids)| is_word(word_id) && synset_ids.iter().all(is_synset))
+    }
+
+    /// Looks up synonyms from the default sources (see [`Source::DEFAULT`]).
+    pub fn lookup<'a>(
+        &'a self,
+        word: &str,
+        lexicon: &Lexicon,
+    ) -> impl Iterator<Item = Cow<'a, Word>> {
+        self.lookup_from(word, Source::DEFAULT, lexicon)
+            .into_iter()
+            .map(|synonym| synonym.word)
+    }
+
+    /// Looks up synonyms from the given sources. Each synonym is returned once, tagged with
+    /// every source that suggested it.
+    ///
+    /// The word is matched regardless of case, accents and punctuation, so "cafe" finds "café"
+    /// and "jack o lantern" finds "jack-o'-lantern".
+    ///
+    /// If the word isn't in the thesaurus, but Wordnet's stemmer suggests a base form that is,
+    /// then the synonyms of the base form are inflected to match the word. So a lookup of "dogs"
+    /// can return "puppies". Synonyms are only inflected to forms in the lexicon, and synonyms
+    /// with no such form are left out.
+    pub fn lookup_from<'a>(
+        &'a self,
+        word: &str,
+        sources: &[Source],
+        lexicon: &Lexicon,
+    ) -> Vec<Synonym<'a>> {
+        self.expand(word, sources, 1, lexicon)
+            .into_iter()
+            .map(|expansion| expansion.synonym)
+            .collect()
+    }
+
+    /// Looks up synonyms of synonyms, up to `depth` hops away from the word. A depth of 1 is
+    /// the same as [`lookup_from`](#method.lookup_from). Deeper expansions leave out the word
+    /// itself, in any spelling.
+    ///
+    /// The search is breadth first, so each candidate is reported at its shortest distance from
+    /// the word, along with the number of distinct shortest paths that reach it and the first
+    /// such path found.
+    pub fn expand<'a>(
+        &'a self,
+        word: &str,
+        sources: &[Source],
+        depth: usize,
+        lexicon: &Lexicon,
+    ) -> Vec<Expansion<'a>> {
+        let key = phrase_key(word);
+        if let Some(word_ids) = self.lookup_word(&key) {
+            return self.walk(word_ids, sources, depth);
+        }
+
+        for stemmed in stem_phrase(&key) {
+            if let Some(word_ids) = self.lookup_word(&stemmed.base_form) {
+                let expansions = self.walk(word_ids, sources, depth);
+                return match Inflection::detect(&key, &stemmed) {
+                    Some(inflection) => {
+                        merge_duplicates(expansions.into_iter().filter_map(|expansion| {
+                            expansion.inflect(stemmed.word_type, inflection, lexicon)
+                        }))
+                    }
+                    None => expansions,
+                };
+            }
         }
 
-        let known_terms = terms.into_iter().map(Word::new).collect::<Vec<_>>();
-        Thesaurus {
-            entries,
-            known_terms,
-            words_lookup: term_id_map,
+        Vec::new()
+    }
+
+    fn walk(&self, start: &[WordId], sources: &[Source], depth: usize) -> Vec<Expansion<'_>> {
+        // The word itself is where every path starts, so paths through it don't count as hops.
+        let mut visits: HashMap<WordId, Visit> = start
+            .iter()
+            .map(|&id| {
+                let visit = Visit {
+                    distance: 0,
+                    paths: 1,
+                    via: Vec::new(),
+                    sources: SmallVec::new(),
+                };
+                (id, visit)
+            })
+            .collect();
+        let mut frontier = start.to_vec();
+        // Start words that are listed among their own synonyms, as a headword is in its entry.
+        // A plain lookup reports them, but deeper expansions leave them out.
+        let mut echoes: HashMap<WordId, Visit> = HashMap::new();
+
+        for distance in 1..=depth {
+            let mut next = Vec::new();
+            for &from in &frontier {
+                let visit = &visits[&from];
+                let paths = visit.paths;
+                let via = if visit.distance == 0 {
+                    Vec::new()
+                } else {
+                    [&visit.via[..], &[from]].concat()
+                };
+                // Visit neighbours in a fixed order, so the reported path doesn't depend on
+                // hash map ordering.
+                let mut neighbours = self
+                    .list_synonyms(from, sources)
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                neighbours.sort_unstable_by_key(|&(id, _)| id);
+                for (to, edge_sources) in neighbours {
+                    match visits.get_mut(&to) {
+                        Some(visit) if visit.distance == distance => {
+                            visit.paths += paths;
+                            for source in edge_sources {
+                                if !visit.sources.contains(&source) {
+                                    visit.sources.push(source);
+                                }
+                            }
+                        }
+                        Some(visit) if visit.distance == 0 => {
+                            if depth == 1 {
+                                let echo = echoes.entry(to).or_insert_with(|| Visit {
+                                    distance,
+                                    paths: 0,
+                                    via: Vec::new(),
+                                    sources: SmallVec::new(),
+                                });
+                                echo.paths += paths;
+                                for source in edge_sources {
+                                    if !echo.sources.contains(&source) {
+                                        echo.sources.push(source);
+                                    }
+                                }
+                            }
+                        }
+                        Some(_) => {
+                            // Already reached by a shorter path
+                        }
+                        None => {
+                            visits.insert(
+                                to,
+                                Visit {
+                                    distance,
+                                    paths,
+                                    via: via.clone(),
+                                    sources: edge_sources,
+                                },
+                            );
+                            next.push(to);
+                        }
+                    }
+                }
+            }
+            frontier = next;
         }
+
+        visits
+            .into_iter()
+            .filter(|(_, visit)| visit.distance > 0)
+            .chain(echoes)
+            .map(|(id, mut visit)| {
+                visit.sources.sort_unstable();
+                Expansion {
+                    synonym: Synonym {
+                        word: Cow::Borrowed(&self.known_terms[id as usize]),
+                        sources: visit.sources,
+                    },
+                    distance: visit.distance,
+                    paths: visit.paths,
+                    via: visit
+                        .via
+                        .iter()
+                        .map(|&id| &self.known_terms[id as usize])
+                        .collect(),
+                }
+            })
+            .collect()
     }
 
-    pub fn push(&mut self, synonyms: &[&'static str]) {
-        let mut word_ids = Vec::new();
-        for term in synonyms {
-            let id = if let Some(&id) = self.words_lookup.get(term) {
-                id
-            } else {
-                let id = self.known_terms.len() as u32;
-                self.known_terms.push(Word::new(term));
-                self.words_lookup.insert(term, id);
-                id
+    /// Lists the direct synonyms of a word, along with the sources that suggested each one.
+    fn list_synonyms(
+        &self,
+        word_id: WordId,
+        sources: &[Source],
+    ) -> HashMap<WordId, SmallVec<[Source; 4]>> {
+        let mut found: HashMap<WordId, SmallVec<[Source; 4]>> = HashMap::new();
+        for &source in sources.iter().unique() {
+            let words = match source {
+                Source::Thesaurus => self
+                    .list_single_entry(word_id)
+                    .unwrap_or_else(|| self.list_match_headwords(word_id)),
+                Source::Wordnet | Source::Similar | Source::Hypernym => {
+                    self.list_wordnet(word_id, source)
+                }
             };
-            word_ids.push(id);
+            for id in words {
+                found.entry(id).or_default().push(source);
+            }
         }
-        self.entries.push(Entry { word_ids });
+        found
     }
 
-    pub fn lookup<'a>(&'a self, word: &str) -> impl Iterator<Item = &'a Word> {
-        let words = if let Some(word_id) = self.lookup_word(word) {
-            self.list_single_entry(word_id)
-                .unwrap_or_else(|| self.list_match_headwords(word_id))
+    /// Returns the id of a term, adding it to the list of known terms if necessary.
+    fn intern(&mut self, term: &str) -> WordId {
+        if let Some(&id) = self.words_lookup.get(term) {
+            id
         } else {
-            HashSet::new()
-        };
+            let id = self.known_terms.len() as WordId;
+            self.known_terms.push(Word::new(term.to_string()));
+            self.words_lookup.insert(term.to_string(), id);
+            self.keys_lookup
+                .entry(phrase_key(term))
+                .or_default()
+                .push(id);
+            id
+        }
+    }
 
-        words
-            .into_iter()
-            .map(|id| &self.known_terms[id as usize])
-            .collect::<Vec<_>>()
+    /// The entries that a word appears in.
+    fn entries_with(&self, word_id: WordId) -> impl Iterator<Item = &Entry> {
+        self.word_entries
+            .get(&word_id)
             .into_iter()
+            .flatten()
+            .map(move |&entry_id| &self.entries[entry_id as usize])
     }
 
     fn list_single_entry(&self, word_id: WordId) -> Option<HashSet<WordId>> {
-        self.entries
-            .iter()
+        self.entries_with(word_id)
             .find(|entry| entry.word_ids[0] == word_id)
-            .map(|entry| {
-                println!("Found entry");
-                entry.word_ids.iter().cloned().collect()
-            })
+            .map(|entry| entry.word_ids.iter().cloned().collect())
     }
 
     fn list_match_headwords(&self, word_id: WordId) -> HashSet<WordId> {
-        println!("Listing all matches");
-        self.entries
-            .iter()
-            .filter(|entry| entry.word_ids.contains(&word_id))
+        self.entries_with(word_id)
             .map(|entry| entry.word_ids[0])
             .collect()
     }
 
-    fn lookup_word(&self, word: &str) -> Option<WordId> {
-        self.words_lookup.get(word).map(Clone::clone)
+    fn list_wordnet(&self, word_id: WordId, source: Source) -> HashSet<WordId> {
+        let mut words = HashSet::new();
+        for &synset_id in self.word_synsets.get(&word_id).into_iter().flatten() {
+            let synset = &self.synsets[synset_id as usize];
+            let related = match source {
+                Source::Similar => &synset.similar[..],
+                Source::Hypernym => &synset.hypernyms[..],
+                _ => std::slice::from_ref(&synset_id),
+            };
+            for &id in related {
+                words.extend(&self.synsets[id as usize].word_ids);
+            }
+        }
+        words
+    }
+
+    /// Returns the ids of all terms that fold to `key`.
+    fn lookup_word(&self, key: &str) -> Option<&[WordId]> {
+        self.keys_lookup.get(key).map(|ids| &ids[..])
     }
 }
 
+/// Combines candidates that have become the same word after inflection, e.g. "dog" and "dogs"
+/// both inflect to "dogs". The nearer candidate wins, and paths of the same length are added up.
+fn merge_duplicates<'a, I>(expansions: I) -> Vec<Expansion<'a>>
+where
+    I: Iterator<Item = Expansion<'a>>,
+{
+    let mut merged: Vec<Expansion> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for expansion in expansions {
+        if let Some(&pos) = positions.get(&expansion.synonym.word.term) {
+            let existing = &mut merged[pos];
+            if expansion.distance < existing.distance {
+                existing.distance = expansion.distance;
+                existing.paths = expansion.paths;
+                existing.via = expansion.via;
+            } else if expansion.distance == existing.distance {
+                existing.paths += expansion.paths;
+            }
+            let sources = &mut existing.synonym.sources;
+            for source in expansion.synonym.sources {
+                if !sources.contains(&source) {
+                    sources.push(source);
+                }
+            }
+            sources.sort_unstable();
+        } else {
+            positions.insert(expansion.synonym.word.term.clone(), merged.len());
+            merged.push(expansion);
+        }
+    }
+    merged
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    fn no_words() -> Lexicon {
+        Lexicon::new(std::iter::empty())
+    }
+
     #[test]
     pub fn pack_word_lengths_single() {
         let lengths = WordLengths::new(&[8]);
@@ -214,7 +644,10 @@ mod test {
     #[test]
     pub fn lookup_headword_found() {
         let t = example_thesaurus();
-        let mut matches = t.lookup("dog").map(|t| t.term).collect::<Vec<_>>();
+        let mut matches = t
+            .lookup("dog", &no_words())
+            .map(|t| t.term.clone())
+            .collect::<Vec<_>>();
         matches.sort_unstable();
         assert_eq!(matches, vec!["dog", "puppy"]);
     }
@@ -222,7 +655,10 @@ mod test {
     #[test]
     pub fn lookup_headword_not_found() {
         let t = example_thesaurus();
-        let mut matches = t.lookup("kid").map(|t| t.term).collect::<Vec<_>>();
+        let mut matches = t
+            .lookup("kid", &no_words())
+            .map(|t| t.term.clone())
+            .collect::<Vec<_>>();
         matches.sort_unstable();
         assert_eq!(matches, vec!["goat", "human"]);
     }
@@ -230,7 +666,292 @@ mod test {
     #[test]
     pub fn lookup_word_not_found_anywhere() {
         let t = example_thesaurus();
-        let mut matches = t.lookup("kitten");
+        let mut matches = t.lookup("kitten", &no_words());
         assert_eq!(matches.next(), None);
     }
+
+    #[test]
+    pub fn lookup_headword_ignores_case() {
+        let mut t = Thesaurus::new();
+        t.push(&["Paris", "City of Light"]);
+        let mut matches = t
+            .lookup("paris", &no_words())
+            .map(|t| t.term.clone())
+            .collect::<Vec<_>>();
+        matches.sort_unstable();
+        assert_eq!(matches, vec!["City of Light", "Paris"]);
+        assert_eq!(t.lookup("CITY OF LIGHT", &no_words()).count(), 1);
+    }
+
+    #[test]
+    pub fn lookup_headword_ignores_accents() {
+        let mut t = Thesaurus::new();
+        t.push(&["café", "bistro"]);
+        let mut matches = t
+            .lookup("cafe", &no_words())
+            .map(|t| t.term.clone())
+            .collect::<Vec<_>>();
+        matches.sort_unstable();
+        assert_eq!(matches, vec!["bistro", "café"]);
+    }
+
+    #[test]
+    pub fn lookup_phrase_ignores_punctuation() {
+        let mut t = Thesaurus::new();
+        t.push(&["jack-o'-lantern", "pumpkin"]);
+        t.push(&["bee's knees", "cat's whiskers"]);
+        assert_eq!(t.lookup("jack o lantern", &no_words()).count(), 2);
+        assert_eq!(t.lookup("Jack-o-Lantern", &no_words()).count(), 2);
+        let mut matches = t
+            .lookup("bees  knees", &no_words())
+            .map(|t| t.term.clone())
+            .collect::<Vec<_>>();
+        matches.sort_unstable();
+        assert_eq!(matches, vec!["bee's knees", "cat's whiskers"]);
+    }
+
+    #[test]
+    pub fn lookup_headword_merges_variants() {
+        // Both spellings are kept for display, and a lookup of either finds both entries.
+        let mut t = Thesaurus::new();
+        t.push(&["Turkey", "Anatolia"]);
+        t.push(&["turkey", "gobbler"]);
+        let mut matches = t
+            .lookup("turkey", &no_words())
+            .map(|t| t.term.clone())
+            .collect::<Vec<_>>();
+        matches.sort_unstable();
+        assert_eq!(matches, vec!["Anatolia", "Turkey", "gobbler", "turkey"]);
+    }
+
+    fn wordnet_thesaurus() -> Thesaurus {
+        let mut t = example_thesaurus();
+        let synsets = vec![
+            "02086723 05 n 03 dog 0 domestic_dog 0 Canis_familiaris 0 001 @ 02085998 n 0000 | a member of the genus Canis",
+            "02085998 05 n 01 canine 0 000 | any of various fissiped mammals",
+            "00003356 00 a 01 nascent 0 001 & 00003552 a 0000 | being born or beginning",
+            "00003552 00 s 02 emergent 0 emerging 0 001 & 00003356 a 0000 | coming into existence",
+        ];
+        t.import_wordnet(synsets.into_iter().map(|line| line.parse().unwrap()));
+        t
+    }
+
+    fn lookup_sorted(
+        t: &Thesaurus,
+        word: &str,
+        sources: &[Source],
+        lexicon: &Lexicon,
+    ) -> Vec<(String, Vec<Source>)> {
+        let mut matches = t
+            .lookup_from(word, sources, lexicon)
+            .into_iter()
+            .map(|synonym| (synonym.word.term.to_string(), synonym.sources.to_vec()))
+            .collect::<Vec<_>>();
+        matches.sort();
+        matches
+    }
+
+    #[test]
+    pub fn lookup_merges_wordnet_synset() {
+        use Source::*;
+        let t = wordnet_thesaurus();
+        assert_eq!(
+            lookup_sorted(&t, "dog", Source::DEFAULT, &no_words()),
+            vec![
+                ("Canis familiaris".to_string(), vec![Wordnet]),
+                ("dog".to_string(), vec![Thesaurus, Wordnet]),
+                ("domestic dog".to_string(), vec![Wordnet]),
+                ("puppy".to_string(), vec![Thesaurus]),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn lookup_single_source() {
+        let t = wordnet_thesaurus();
+        let matches = lookup_sorted(&t, "dog", &[Source::Thesaurus], &no_words());
+        assert_eq!(
+            matches.into_iter().map(|(w, _)| w).collect::<Vec<_>>(),
+            vec!["dog", "puppy"]
+        );
+    }
+
+    #[test]
+    pub fn lookup_hypernyms() {
+        let t = wordnet_thesaurus();
+        assert_eq!(
+            lookup_sorted(&t, "domestic dog", &[Source::Hypernym], &no_words()),
+            vec![("canine".to_string(), vec![Source::Hypernym])]
+        );
+    }
+
+    #[test]
+    pub fn lookup_similar() {
+        let t = wordnet_thesaurus();
+        let matches = lookup_sorted(&t, "emerging", &[Source::Similar], &no_words());
+        assert_eq!(
+            matches,
+            vec![("nascent".to_string(), vec![Source::Similar])]
+        );
+    }
+
+    #[test]
+    pub fn lookup_plural_noun() {
+        let t = example_thesaurus();
+        let lexicon = Lexicon::new(vec!["dog", "dogs", "puppies", "puppy"].into_iter());
+        let mut matches = t
+            .lookup("dogs", &lexicon)
+            .map(|t| t.term.clone())
+            .collect::<Vec<_>>();
+        matches.sort_unstable();
+        assert_eq!(matches, vec!["dogs", "puppies"]);
+    }
+
+    #[test]
+    pub fn lookup_past_tense_verb() {
+        let mut t = Thesaurus::new();
+        t.push(&["run", "sprint", "take flight"]);
+        let lexicon = Lexicon::new(vec!["ran", "sprinted", "sprints", "took", "taken"].into_iter());
+        let mut matches = t
+            .lookup("ran", &lexicon)
+            .map(|t| t.term.clone())
+            .collect::<Vec<_>>();
+        matches.sort_unstable();
+        assert_eq!(matches, vec!["ran", "sprinted", "took flight"]);
+    }
+
+    #[test]
+    pub fn lookup_comparative_adjective() {
+        // "rapider" follows the rules, but it isn't a word.
+        let mut t = Thesaurus::new();
+        t.push(&["quick", "fast", "rapid"]);
+        let lexicon = Lexicon::new(vec!["faster", "quicker", "rapid", "rapidly"].into_iter());
+        let mut matches = t
+            .lookup("quicker", &lexicon)
+            .map(|t| t.term.clone())
+            .collect::<Vec<_>>();
+        matches.sort_unstable();
+        assert_eq!(matches, vec!["faster", "quicker"]);
+    }
+
+    #[test]
+    pub fn lookup_merges_inflected_duplicates() {
+        let mut t = Thesaurus::new();
+        t.push(&["cat", "moggy", "moggie"]);
+        let lexicon = Lexicon::new(vec!["cats", "moggies"].into_iter());
+        let matches = lookup_sorted(&t, "cats", Source::DEFAULT, &lexicon);
+        assert_eq!(
+            matches,
+            vec![
+                ("cats".to_string(), vec![Source::Thesaurus]),
+                ("moggies".to_string(), vec![Source::Thesaurus]),
+            ]
+        );
+    }
+
+    fn expand_sorted(t: &Thesaurus, word: &str, depth: usize) -> Vec<(String, usize, u32, String)> {
+        let mut matches = t
+            .expand(word, &[Source::Thesaurus], depth, &no_words())
+            .into_iter()
+            .map(|e| {
+                let via = e.via.iter().map(|w| w.term.as_str()).join(" > ");
+                (e.synonym.word.term.to_string(), e.distance, e.paths, via)
+            })
+            .collect::<Vec<_>>();
+        matches.sort();
+        matches
+    }
+
+    #[test]
+    pub fn expand_depth_one_is_lookup() {
+        let t = example_thesaurus();
+        let matches = expand_sorted(&t, "dog", 1);
+        assert_eq!(
+            matches,
+            vec![
+                ("dog".to_string(), 1, 1, "".to_string()),
+                ("puppy".to_string(), 1, 1, "".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn expand_two_hops() {
+        let mut t = Thesaurus::new();
+        t.push(&["happy", "glad", "cheerful"]);
+        t.push(&["glad", "pleased", "delighted"]);
+        t.push(&["cheerful", "sunny", "delighted"]);
+        let matches = expand_sorted(&t, "happy", 2);
+        assert_eq!(
+            matches,
+            vec![
+                ("cheerful".to_string(), 1, 1, "".to_string()),
+                ("delighted".to_string(), 2, 2, "glad".to_string()),
+                ("glad".to_string(), 1, 1, "".to_string()),
+                ("pleased".to_string(), 2, 1, "glad".to_string()),
+                ("sunny".to_string(), 2, 1, "cheerful".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn expand_from_every_spelling() {
+        // "café" and "cafe" are both the word being looked up, so paths through either of them
+        // start from scratch.
+        let mut t = Thesaurus::new();
+        t.push(&["café", "cafe", "bistro"]);
+        t.push(&["cafe", "coffee shop"]);
+        t.push(&["bistro", "brasserie"]);
+        let matches = expand_sorted(&t, "cafe", 2);
+        assert_eq!(
+            matches,
+            vec![
+                ("bistro".to_string(), 1, 1, "".to_string()),
+                ("brasserie".to_string(), 2, 1, "bistro".to_string()),
+                ("coffee shop".to_string(), 1, 1, "".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn expand_scores_by_paths_and_distance() {
+        let mut t = Thesaurus::new();
+        t.push(&["happy", "glad", "cheerful"]);
+        t.push(&["glad", "pleased", "delighted"]);
+        t.push(&["cheerful", "sunny", "delighted"]);
+        let expansions = t.expand("happy", &[Source::Thesaurus], 2, &no_words());
+        let score = |term: &str| {
+            expansions
+                .iter()
+                .find(|e| e.synonym.word.term == term)
+                .map(Expansion::score)
+                .unwrap()
+        };
+        assert!(score("glad") > score("delighted"));
+        assert!(score("delighted") > score("sunny"));
+    }
+
+    #[test]
+    pub fn check_ids() {
+        assert!(wordnet_thesaurus().is_consistent());
+
+        let mut t = wordnet_thesaurus();
+        t.synsets[0].hypernyms.push(4);
+        assert!(!t.is_consistent());
+
+        let mut t = wordnet_thesaurus();
+        let unknown = t.known_terms.len() as WordId;
+        t.word_synsets.insert(unknown, vec![0]);
+        assert!(!t.is_consistent());
+
+        let mut t = wordnet_thesaurus();
+        t.entries.push(Entry::default());
+        assert!(!t.is_consistent());
+    }
+
+    #[test]
+    pub fn parse_source() {
+        assert_eq!("wordnet".parse::<Source>().unwrap(), Source::Wordnet);
+        assert!("roget".parse::<Source>().is_err());
+    }
 }
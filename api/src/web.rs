@@ -1,18 +1,30 @@
+mod advanced;
+mod data_paths;
 mod preview;
 mod refdata;
+mod stream;
 
 use crate::Options;
 use preview::Preview;
 use refdata::Reference;
 
+pub use refdata::build_index;
+
 use actix_files as fs;
 use actix_web::http::StatusCode;
-use actix_web::{middleware, web, App, HttpResponse, HttpServer};
+use actix_web::{middleware, web, App, HttpResponse, HttpServer, ResponseError};
 use anyhow::{ensure, Context, Result};
 use itertools::Itertools;
 use listenfd::ListenFd;
+use rayon::ThreadPool;
 use serde::{Deserialize, Serialize};
-use wordfun::{plural, Dictionary, Popularity, Results};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use wordfun::{
+    plural, Anagram, Appearance, Dictionary, Entry, FindWord, Inflected, Lexicon, Matches,
+    ParseError, Popularity, Ranked, Results, SearchKey, Source, Tag, Tagged, Tags, WordType,
+};
 
 /// Request type for a preview query (anagram, find-word, or thesaurus)
 #[derive(Deserialize)]
@@ -20,15 +32,123 @@ struct PreviewQuery {
     /// The search term being requested. Each of the searches will interpret this string
     /// differently, but what they have in common is that they all take a single string as input.
     ///
-    /// (At some point, there may be an "advanced search" feature that will take a richer data
-    /// object as input, but this is what we use for now!)
+    /// (The advanced search at `POST /search` takes a richer data object as input; see
+    /// [`advanced::SearchRequest`].)
+    q: String,
+    /// An optional comma-separated list of parts of speech, e.g. "noun,verb". If given, only
+    /// words that could be one of them are returned.
+    pos: Option<String>,
+    /// An optional comma-separated list of categories, e.g. "location" or "person,proper_noun".
+    /// If given, only anagram and find-word results with one of these tags are returned.
+    category: Option<String>,
+}
+
+/// Request type for a full anagram or find-word search. This takes the same parameters as a
+/// preview, plus some for sorting and paging through the results.
+#[derive(Deserialize)]
+struct FullQuery {
+    q: String,
+    pos: Option<String>,
+    category: Option<String>,
+    /// How to order the results. The default is most popular first.
+    #[serde(default)]
+    sort: SortOrder,
+    /// The number of results to skip.
+    #[serde(default)]
+    offset: usize,
+    /// The most results to return. If this is missing, we return all of them.
+    limit: Option<usize>,
+}
+
+/// The orders that full results can be sorted in
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum SortOrder {
+    /// Most popular first
+    Popularity,
+    /// Alphabetical, ignoring case
+    Alphabetical,
+    /// Shortest first, then most popular first
+    Length,
+}
+
+impl Default for SortOrder {
+    fn default() -> Self {
+        SortOrder::Popularity
+    }
+}
+
+/// Request type for a thesaurus query
+#[derive(Deserialize)]
+struct ThesaurusQuery {
+    /// The word or phrase to look up.
+    q: String,
+    /// A comma-separated list of synonym sources, e.g. "thesaurus,wordnet,hypernym". If this
+    /// is missing, we use the bundled thesaurus and Wordnet synsets. A source we don't know is
+    /// a bad query.
+    sources: Option<String>,
+    /// An optional find-word pattern such as "..t/...." that synonyms must match. This is
+    /// handy when you already know the length and some of the crossing letters.
+    pattern: Option<String>,
+    /// How many hops to take through the thesaurus. The default of 1 returns direct synonyms;
+    /// 2 also returns synonyms of synonyms, and so on, up to `MAX_THESAURUS_DEPTH`.
+    depth: Option<usize>,
+    /// An optional comma-separated list of parts of speech that synonyms must have.
+    pos: Option<String>,
+}
+
+/// The deepest thesaurus expansion we allow. Each extra hop multiplies the number of results.
+const MAX_THESAURUS_DEPTH: usize = 3;
+
+/// Request type for a reverse dictionary query
+#[derive(Deserialize)]
+struct DefineQuery {
+    /// A definition, or part of one, such as "large flightless bird".
     q: String,
+    /// An optional find-word pattern that answers must match.
+    pattern: Option<String>,
+    /// An optional number of letters that answers must have.
+    len: Option<usize>,
+    /// An optional comma-separated list of parts of speech that answers must have.
+    pos: Option<String>,
 }
 
+/// Request type for an inflection query
+#[derive(Deserialize)]
+struct InflectQuery {
+    /// The base form to inflect, such as "run".
+    q: String,
+    /// An optional comma-separated list of parts of speech to inflect it as.
+    pos: Option<String>,
+}
+
+/// Request type for a clue search
+#[derive(Deserialize)]
+struct ClueQuery {
+    /// Words that must all appear in the clue, such as "sea eagle". This can be left empty
+    /// to list every clue for answers matching `pattern`.
+    #[serde(default)]
+    q: String,
+    /// An optional find-word pattern that answers must match.
+    pattern: Option<String>,
+    /// An optional comma-separated list of parts of speech that answers must have.
+    pos: Option<String>,
+}
+
+/// The most clues we return from a clue search.
+const MAX_CLUE_RESULTS: usize = 20;
+
+/// The most words we return from a reverse dictionary query.
+const MAX_DEFINE_RESULTS: usize = 20;
+
 /// Result type for a full anagram or find-word search
 #[derive(Serialize)]
 struct FullResults {
-    /// The result set is just a list of entries.
+    /// The total number of matching words, including the ones outside this page
+    total: usize,
+    /// The position of the first word in this page
+    offset: usize,
+    /// The result set is just a list of entries, in the order requested.
     words: Vec<Match>,
 }
 
@@ -39,11 +159,23 @@ struct Match {
     word: String,
     /// If we have a definition for the word, it goes here
     definition: Option<String>,
+    /// The name of the dictionary the definition came from, such as "wordnet"
+    source: Option<String>,
+    /// The parts of speech the word could be, going by the dictionary. This is empty for
+    /// words the dictionary doesn't know.
+    word_types: Vec<WordType>,
     /// Used for highlighting rows in the full results.  The current rule is that if
     /// score is set to something greater than 0, the row is highlighted.
     score: Option<u32>,
+    /// The number of times the word has been an answer in a past puzzle
+    appearances: usize,
+    /// Some of the word's most recent clues in past puzzles
+    clues: Vec<Appearance>,
 }
 
+/// The most past clues we show for each word in the full results.
+const MAX_PAST_CLUES: usize = 3;
+
 /// The response for a thesaurus query
 #[derive(Serialize)]
 struct ThesaurusResponse {
@@ -66,6 +198,165 @@ struct ThesaurusResponse {
     ///   +------------+--------------------------------+
     ///
     words: Vec<(String, Vec<String>)>,
+    /// The sources that suggested each word in `words`, so the UI can show where a synonym came
+    /// from.
+    provenance: BTreeMap<String, Vec<Source>>,
+    /// For words that aren't direct synonyms, the chain of words that led to them.
+    via: BTreeMap<String, Vec<String>>,
+}
+
+/// The response for a reverse dictionary query
+#[derive(Serialize)]
+struct DefineResponse {
+    /// Total number of matches, as a string like "1 match" or "137 matches"
+    count: String,
+    /// The definition we looked up
+    query: String,
+    /// The best matching words, best first
+    words: Vec<DefinedWord>,
+}
+
+/// A single word in the response to a reverse dictionary query
+#[derive(Serialize)]
+struct DefinedWord {
+    word: String,
+    /// The part of speech, if the dictionary says
+    word_type: Option<WordType>,
+    /// The definition that matched the query
+    definition: String,
+    /// The name of the dictionary the definition came from
+    source: String,
+}
+
+/// The response for a clue search
+#[derive(Serialize)]
+struct ClueResponse {
+    /// Total number of matches, as a string like "1 match" or "137 matches"
+    count: String,
+    /// The words we searched for
+    query: String,
+    /// The matching clues, most recent first
+    clues: Vec<ClueMatch>,
+}
+
+/// A single clue in the response to a clue search
+#[derive(Serialize)]
+struct ClueMatch {
+    /// The answer as it was published
+    answer: String,
+    /// The lengths of the words in the answer, such as "4,5"
+    enumeration: String,
+    clue: String,
+    publication: String,
+    date: String,
+}
+
+/// The response for an inflection query
+#[derive(Serialize)]
+struct InflectResponse {
+    /// The base form we inflected
+    query: String,
+    /// The parts of speech we tried. These come from the dictionary if it knows the word;
+    /// otherwise, we try them all.
+    word_types: Vec<WordType>,
+    /// Inflections of the base form that are in the lexicon
+    words: Vec<Inflected>,
+}
+
+/// The response for a query we couldn't parse
+#[derive(Serialize)]
+struct ErrorResponse<'a> {
+    /// A description of the problem, such as "unexpected '?' at position 2"
+    message: String,
+    /// The kind of problem, as `error`, and where it was, as `position` or `parameter` and `name`
+    #[serde(flatten)]
+    details: &'a BadQuery,
+}
+
+/// A query we couldn't parse, which we serve as a 400 with an [`ErrorResponse`].
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum BadQuery {
+    /// An anagram or find-word pattern we couldn't parse
+    Pattern(ParseError),
+    /// A name we don't recognize in a comma-separated list
+    UnknownName(UnknownName),
+}
+
+/// A name we don't recognize in a comma-separated list parameter, such as "nuon" in
+/// `pos=nuon,verb`
+#[derive(Debug, Serialize)]
+#[serde(tag = "error", rename = "unknown_name")]
+struct UnknownName {
+    /// The parameter the list was in
+    parameter: &'static str,
+    /// The name we didn't recognize
+    name: String,
+    /// What the parser made of it, such as "unknown part of speech \"nuon\""
+    #[serde(skip)]
+    message: String,
+}
+
+impl From<ParseError> for BadQuery {
+    fn from(error: ParseError) -> Self {
+        BadQuery::Pattern(error)
+    }
+}
+
+impl fmt::Display for BadQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BadQuery::Pattern(error) => error.fmt(f),
+            BadQuery::UnknownName(unknown) => f.write_str(&unknown.message),
+        }
+    }
+}
+
+impl ResponseError for BadQuery {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::BadRequest().json(ErrorResponse {
+            message: self.to_string(),
+            details: self,
+        })
+    }
+}
+
+/// Parses a comma-separated list parameter, such as `sources=thesaurus,wordnet`. Returns
+/// `None` if there's no list, meaning anything will do, or an error naming the first item we
+/// don't recognize.
+fn parse_list<T>(parameter: &'static str, list: Option<&str>) -> Result<Option<Vec<T>>, BadQuery>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    list.filter(|list| !list.trim().is_empty())
+        .map(|list| {
+            list.split(',')
+                .map(|name| {
+                    let name = name.trim();
+                    name.parse().map_err(|error: T::Err| {
+                        BadQuery::UnknownName(UnknownName {
+                            parameter,
+                            name: name.to_string(),
+                            message: error.to_string(),
+                        })
+                    })
+                })
+                .collect()
+        })
+        .transpose()
+}
+
+/// Parses an optional find-word pattern, treating an empty one as missing.
+fn parse_pattern(pattern: Option<&str>) -> Result<Option<FindWord>, ParseError> {
+    pattern
+        .filter(|p| !p.is_empty())
+        .map(FindWord::parse)
+        .transpose()
 }
 
 /// Serve a static HTML page for the root path when running in API mode
@@ -75,87 +366,360 @@ async fn api_index() -> HttpResponse {
         .body(include_str!("./api-index.html"))
 }
 
+/// Parses a comma-separated list of parts of speech. Returns `None` if there's no list,
+/// meaning any part of speech will do.
+fn parse_pos(pos: Option<&str>) -> Result<Option<Vec<WordType>>, BadQuery> {
+    parse_list("pos", pos)
+}
+
+/// Keeps only the results that could be one of the given parts of speech, if there are any.
+fn retain_pos<'a>(
+    results: Results<'a>,
+    pos: Option<Vec<WordType>>,
+    dict: &'a Dictionary,
+) -> Results<'a> {
+    match pos {
+        Some(word_types) => results.retain(move |word| has_pos(word, &word_types, dict)),
+        None => results,
+    }
+}
+
+fn has_pos(word: &str, word_types: &[WordType], dict: &Dictionary) -> bool {
+    dict.guess_word_types(word)
+        .iter()
+        .any(|word_type| word_types.contains(word_type))
+}
+
+/// Parses a comma-separated list of categories. Returns `None` if there's no list, meaning
+/// any category will do.
+fn parse_categories(category: Option<&str>) -> Result<Option<Tags>, BadQuery> {
+    let tags: Option<Vec<Tag>> = parse_list("category", category)?;
+    Ok(tags.map(|tags| tags.into_iter().collect()))
+}
+
+/// Searches the lexicon, keeping only entries with one of the tags if there are any. If there's
+/// a `pool`, the search is split across its threads, but the results only arrive once it's
+/// finished.
+fn search<'a, M>(
+    lexicon: &'a Lexicon,
+    pattern: M,
+    tags: Option<Tags>,
+    pool: Option<&ThreadPool>,
+) -> Results<'a>
+where
+    M: Matches + Into<SearchKey> + Clone + Sync + 'a,
+{
+    match (tags, pool) {
+        (Some(tags), Some(pool)) => lexicon.par_search(Tagged::new(pattern, tags), pool),
+        (Some(tags), None) => lexicon.search(Tagged::new(pattern, tags)),
+        (None, Some(pool)) => lexicon.par_search(pattern, pool),
+        (None, None) => lexicon.search(pattern),
+    }
+}
+
 /// Serve an anagram preview
-async fn preview_an(query: web::Query<PreviewQuery>, r: web::Data<Reference>) -> HttpResponse {
-    let results = r.lexicon().anagram(&query.q);
+async fn preview_an(
+    query: web::Query<PreviewQuery>,
+    r: web::Data<Reference>,
+) -> Result<HttpResponse, BadQuery> {
+    let results = search(
+        r.lexicon(),
+        Anagram::parse(&query.q)?,
+        parse_categories(query.category.as_deref())?,
+        r.search_pool(),
+    );
+    let results = retain_pos(results, parse_pos(query.pos.as_deref())?, r.dictionary());
     let preview = Preview::new(20, 5, r.popularity());
-    HttpResponse::Ok().json(preview.build(&results.key, &results.words))
+    Ok(HttpResponse::Ok().json(preview.build(results)))
 }
 
 /// Serve a find-word preview
-async fn preview_fw(query: web::Query<PreviewQuery>, r: web::Data<Reference>) -> HttpResponse {
-    let results = r.lexicon().find_word(&query.q);
+async fn preview_fw(
+    query: web::Query<PreviewQuery>,
+    r: web::Data<Reference>,
+) -> Result<HttpResponse, BadQuery> {
+    let results = search(
+        r.lexicon(),
+        FindWord::parse(&query.q)?,
+        parse_categories(query.category.as_deref())?,
+        r.search_pool(),
+    );
+    let results = retain_pos(results, parse_pos(query.pos.as_deref())?, r.dictionary());
     let preview = Preview::new(20, 5, r.popularity());
-    HttpResponse::Ok().json(preview.build(&results.key, &results.words))
+    Ok(HttpResponse::Ok().json(preview.build(results)))
 }
 
 /// Serve the full anagram lookup (including definitions)
-async fn full_an(query: web::Query<PreviewQuery>, r: web::Data<Reference>) -> HttpResponse {
-    full_results(
-        r.lexicon().anagram(&query.q),
-        r.dictionary(),
-        r.popularity(),
-    )
+async fn full_an(
+    query: web::Query<FullQuery>,
+    r: web::Data<Reference>,
+) -> Result<HttpResponse, BadQuery> {
+    let results = search(
+        r.lexicon(),
+        Anagram::parse(&query.q)?,
+        parse_categories(query.category.as_deref())?,
+        r.search_pool(),
+    );
+    let words = retain_pos(results, parse_pos(query.pos.as_deref())?, r.dictionary());
+    Ok(full_results(words, &query, &r))
 }
 
 /// Serve the full find-word lookup (including definitions)
-async fn full_fw(query: web::Query<PreviewQuery>, r: web::Data<Reference>) -> HttpResponse {
-    full_results(
-        r.lexicon().find_word(&query.q),
-        r.dictionary(),
-        r.popularity(),
-    )
+async fn full_fw(
+    query: web::Query<FullQuery>,
+    r: web::Data<Reference>,
+) -> Result<HttpResponse, BadQuery> {
+    let results = search(
+        r.lexicon(),
+        FindWord::parse(&query.q)?,
+        parse_categories(query.category.as_deref())?,
+        r.search_pool(),
+    );
+    let words = retain_pos(results, parse_pos(query.pos.as_deref())?, r.dictionary());
+    Ok(full_results(words, &query, &r))
+}
+
+/// Sorts the words in the requested order.
+fn sort_words(words: &mut Vec<&str>, order: SortOrder, popularity: &Popularity) {
+    match order {
+        SortOrder::Popularity => popularity.sort(words),
+        SortOrder::Alphabetical => words.sort_by_cached_key(|word| word.to_lowercase()),
+        SortOrder::Length => {
+            popularity.sort(words);
+            // The sort is stable, so words of the same length stay in order of popularity.
+            words.sort_by_cached_key(|word| Entry::from(*word).word_length());
+        }
+    }
+}
+
+/// Serves one page of full results. Only the words in the page are looked up in the
+/// dictionary, so paging through a big result set is cheap.
+fn full_results<'a, I>(words: I, query: &FullQuery, r: &Reference) -> HttpResponse
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    HttpResponse::Ok().json(full_page(words, query.sort, query.offset, query.limit, r))
 }
 
-fn full_results<'a>(rs: Results<'a>, dict: &Dictionary, popularity: &Popularity) -> HttpResponse {
-    HttpResponse::Ok().json(FullResults {
-        words: rs
+/// Sorts the words, and looks up the ones from `offset` to `offset + limit`. In order of
+/// popularity, a page only needs the `offset + limit` most popular words, so those are picked
+/// out with [`Popularity::top`] instead of sorting them all.
+fn full_page<'a, I>(
+    words: I,
+    sort: SortOrder,
+    offset: usize,
+    limit: Option<usize>,
+    r: &Reference,
+) -> FullResults
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let (words, total) = match (sort, limit) {
+        (SortOrder::Popularity, Some(limit)) => {
+            let (top, total) = r.popularity().top(words, offset.saturating_add(limit));
+            (top.into_iter().map(Ranked::into_inner).collect(), total)
+        }
+        _ => {
+            let mut words: Vec<&str> = words.into_iter().collect();
+            sort_words(&mut words, sort, r.popularity());
+            let total = words.len();
+            (words, total)
+        }
+    };
+    FullResults {
+        total,
+        offset,
+        words: words
             .into_iter()
-            .map(|word| {
-                let definition = dict.lookup(word).map(|s| s.to_string());
-                let score = if popularity.is_ranked(word) {
-                    Some(1)
-                } else {
-                    None
-                };
-                Match {
-                    word: word.to_string(),
-                    score,
-                    definition,
-                }
-            })
+            .skip(offset)
+            .take(limit.unwrap_or(total))
+            .map(|word| full_match(word, r))
             .collect(),
-    })
+    }
+}
+
+/// Looks up everything we know about a word for the full results.
+fn full_match(word: &str, r: &Reference) -> Match {
+    let (dict, popularity, past_answers) = (r.dictionary(), r.popularity(), r.past_answers());
+    let definition = dict.lookup_definition(word);
+    let score = if popularity.is_ranked(word) {
+        Some(1)
+    } else {
+        None
+    };
+    Match {
+        word: word.to_string(),
+        score,
+        definition: definition.map(|defn| defn.text.to_string()),
+        source: definition.map(|defn| defn.source.to_string()),
+        word_types: dict.guess_word_types(word),
+        appearances: past_answers.count(word),
+        clues: past_answers
+            .sample_clues(word, MAX_PAST_CLUES)
+            .into_iter()
+            .cloned()
+            .collect(),
+    }
 }
 
 /// Serve the thesaurus lookup
 async fn preview_thesaurus(
-    params: web::Query<PreviewQuery>,
+    params: web::Query<ThesaurusQuery>,
     r: web::Data<Reference>,
-) -> HttpResponse {
+) -> Result<HttpResponse, BadQuery> {
     let query = params.q.to_string();
-    let mut result = r.thesaurus().lookup(&query).collect::<Vec<_>>();
-    result.sort();
-    result.sort_by_cached_key(|word| &word.word_lengths);
+    let sources = parse_list("sources", params.sources.as_deref())?
+        .unwrap_or_else(|| Source::DEFAULT.to_vec());
+    let depth = params.depth.unwrap_or(1).clamp(1, MAX_THESAURUS_DEPTH);
+    let mut result = r.thesaurus().expand(&query, &sources, depth, r.lexicon());
+    if let Some(pattern) = parse_pattern(params.pattern.as_deref())? {
+        result.retain(|e| pattern.matches(&Entry::from(e.synonym.word.term.as_str())));
+    }
+    if let Some(word_types) = parse_pos(params.pos.as_deref())? {
+        result.retain(|e| has_pos(&e.synonym.word.term, &word_types, r.dictionary()));
+    }
+    // Group by length, and within each group, put the most plausible words first.
+    result.sort_by(|a, b| {
+        let (x, y) = (&a.synonym.word, &b.synonym.word);
+        x.word_lengths
+            .cmp(&y.word_lengths)
+            .then_with(|| b.score().partial_cmp(&a.score()).unwrap())
+            .then_with(|| x.cmp(y))
+    });
     let mut num_words = 0;
     let mut grouped_words = Vec::new();
+    let mut provenance = BTreeMap::new();
+    let mut via = BTreeMap::new();
     for (lengths, group) in result
         .into_iter()
-        .group_by(|word| &word.word_lengths)
+        .group_by(|e| e.synonym.word.word_lengths.clone())
         .into_iter()
     {
-        let words: Vec<_> = group.map(|word| word.to_string()).collect();
+        let mut words = Vec::new();
+        for expansion in group {
+            let word = expansion.synonym.word.to_string();
+            provenance.insert(word.clone(), expansion.synonym.sources.to_vec());
+            if !expansion.via.is_empty() {
+                let path = expansion.via.iter().map(|w| w.to_string()).collect();
+                via.insert(word.clone(), path);
+            }
+            words.push(word);
+        }
         num_words += words.len();
         grouped_words.push((lengths.format(), words));
     }
 
     let count = plural(num_words, "match", "matches");
 
-    HttpResponse::Ok().json(ThesaurusResponse {
+    Ok(HttpResponse::Ok().json(ThesaurusResponse {
         count,
         query,
         words: grouped_words,
-    })
+        provenance,
+        via,
+    }))
+}
+
+/// Serve a reverse dictionary lookup: words whose definitions match the query
+async fn preview_define(
+    params: web::Query<DefineQuery>,
+    r: web::Data<Reference>,
+) -> Result<HttpResponse, BadQuery> {
+    let pattern = parse_pattern(params.pattern.as_deref())?;
+    let word_types = parse_pos(params.pos.as_deref())?;
+    let matches: Vec<_> = r
+        .reverse_dictionary()
+        .search(&params.q)
+        .into_iter()
+        .filter(|m| {
+            let entry = Entry::from(m.word);
+            pattern.as_ref().map_or(true, |p| p.matches(&entry))
+                && params.len.map_or(true, |len| len == entry.word_length())
+                && word_types
+                    .as_ref()
+                    .map_or(true, |wt| m.word_type.map_or(false, |t| wt.contains(&t)))
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(DefineResponse {
+        count: plural(matches.len(), "match", "matches"),
+        query: params.q.to_string(),
+        words: matches
+            .into_iter()
+            .take(MAX_DEFINE_RESULTS)
+            .map(|m| DefinedWord {
+                word: m.word.to_string(),
+                word_type: m.word_type,
+                definition: m.definition.to_string(),
+                source: m.source.to_string(),
+            })
+            .collect(),
+    }))
+}
+
+/// Serve a search of past clues, for finding how other setters clued a word or which answer
+/// fits a half-remembered clue
+async fn preview_clues(
+    params: web::Query<ClueQuery>,
+    r: web::Data<Reference>,
+) -> Result<HttpResponse, BadQuery> {
+    let past_answers = r.past_answers();
+    let pattern = parse_pattern(params.pattern.as_deref())?;
+    let word_types = parse_pos(params.pos.as_deref())?;
+    let candidates: Box<dyn Iterator<Item = &Appearance>> = if !params.q.trim().is_empty() {
+        Box::new(past_answers.search_clues(&params.q).into_iter())
+    } else if pattern.is_some() {
+        Box::new(past_answers.recent())
+    } else {
+        Box::new(std::iter::empty())
+    };
+    let matches: Vec<(Entry, &Appearance)> = candidates
+        .map(|appearance| (Entry::from(appearance.answer.as_str()), appearance))
+        .filter(|(entry, appearance)| {
+            pattern.as_ref().map_or(true, |p| p.matches(entry))
+                && word_types
+                    .as_ref()
+                    .map_or(true, |wt| has_pos(&appearance.answer, wt, r.dictionary()))
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(ClueResponse {
+        count: plural(matches.len(), "match", "matches"),
+        query: params.q.to_string(),
+        clues: matches
+            .into_iter()
+            .take(MAX_CLUE_RESULTS)
+            .map(|(entry, appearance)| ClueMatch {
+                answer: appearance.answer.to_string(),
+                enumeration: entry.enumeration(),
+                clue: appearance.clue.to_string(),
+                publication: appearance.publication.to_string(),
+                date: appearance.date.to_string(),
+            })
+            .collect(),
+    }))
+}
+
+/// Serve the inflections of a base form, e.g. "run" -> "ran", "running", "runs"
+async fn preview_inflect(
+    query: web::Query<InflectQuery>,
+    r: web::Data<Reference>,
+) -> Result<HttpResponse, BadQuery> {
+    let base_form = query.q.trim().to_lowercase();
+    let mut word_types = r.dictionary().word_types(&base_form);
+    if word_types.is_empty() {
+        word_types = vec![WordType::Noun, WordType::Verb, WordType::Adj, WordType::Adv];
+    }
+    if let Some(pos) = parse_pos(query.pos.as_deref())? {
+        word_types.retain(|word_type| pos.contains(word_type));
+    }
+    let words = r.lexicon().inflect(&base_form, &word_types);
+
+    Ok(HttpResponse::Ok().json(InflectResponse {
+        query: base_form,
+        word_types,
+        words,
+    }))
 }
 
 /// Serve the build ID from the `SOURCE_COMMIT` environment variable.
@@ -184,8 +748,14 @@ pub async fn serve(options: Options) -> Result<()> {
             .route("/preview/an", web::get().to(preview_an))
             .route("/preview/fw", web::get().to(preview_fw))
             .route("/preview/thesaurus", web::get().to(preview_thesaurus))
+            .route("/preview/define", web::get().to(preview_define))
+            .route("/preview/inflect", web::get().to(preview_inflect))
+            .route("/preview/clues", web::get().to(preview_clues))
+            .route("/search", web::post().to(advanced::search))
             .route("/words/an", web::get().to(full_an))
             .route("/words/fw", web::get().to(full_fw))
+            .route("/stream/an", web::get().to(stream::stream_an))
+            .route("/stream/fw", web::get().to(stream::stream_fw))
             .route("/version.txt", web::get().to(version));
 
         app = if let Some(assets_dir) = reference.assets_dir() {
@@ -208,3 +778,91 @@ pub async fn serve(options: Options) -> Result<()> {
 
     Ok(server.run().await?)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn error_body(error: &BadQuery) -> Value {
+        serde_json::to_value(ErrorResponse {
+            message: error.to_string(),
+            details: error,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    pub fn pattern_error_body() {
+        let error = BadQuery::from(FindWord::parse("a.b?").unwrap_err());
+        assert_eq!(
+            error_body(&error),
+            json!({
+                "error": "unexpected_character",
+                "message": "unexpected '?' at position 3",
+                "character": "?",
+                "position": 3,
+            })
+        );
+    }
+
+    #[test]
+    pub fn parse_parts_of_speech() {
+        assert_eq!(
+            parse_pos(Some("noun, adj")).unwrap(),
+            Some(vec![WordType::Noun, WordType::Adj])
+        );
+        assert_eq!(parse_pos(Some("")).unwrap(), None);
+
+        let error = parse_pos(Some("verb,nuon")).unwrap_err();
+        assert_eq!(
+            error_body(&error),
+            json!({
+                "error": "unknown_name",
+                "message": "unknown part of speech \"nuon\"",
+                "parameter": "pos",
+                "name": "nuon",
+            })
+        );
+    }
+
+    #[test]
+    pub fn parse_category_list() {
+        let tags = parse_categories(Some("location,person")).unwrap().unwrap();
+        assert!(tags.contains(Tag::Location));
+        assert!(tags.contains(Tag::Person));
+        assert!(!tags.contains(Tag::Animal));
+        assert_eq!(parse_categories(None).unwrap(), None);
+
+        let error = parse_categories(Some("location,planet")).unwrap_err();
+        assert_eq!(
+            error_body(&error),
+            json!({
+                "error": "unknown_name",
+                "message": "unknown category \"planet\"",
+                "parameter": "category",
+                "name": "planet",
+            })
+        );
+    }
+
+    #[test]
+    pub fn parse_sources() {
+        let sources: Option<Vec<Source>> =
+            parse_list("sources", Some("wordnet, hypernym")).unwrap();
+        assert_eq!(sources, Some(vec![Source::Wordnet, Source::Hypernym]));
+        assert_eq!(parse_list::<Source>("sources", Some(" ")).unwrap(), None);
+        assert_eq!(parse_list::<Source>("sources", None).unwrap(), None);
+
+        let error = parse_list::<Source>("sources", Some("thesaurus,wordnut")).unwrap_err();
+        assert_eq!(
+            error_body(&error),
+            json!({
+                "error": "unknown_name",
+                "message": "unknown synonym source \"wordnut\"",
+                "parameter": "sources",
+                "name": "wordnut",
+            })
+        );
+    }
+}
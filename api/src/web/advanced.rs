@@ -0,0 +1,300 @@
+use super::refdata::Reference;
+use super::{full_page, has_pos, parse_pattern, BadQuery, FullResults, SortOrder};
+
+use actix_web::{web, HttpResponse};
+use rayon::ThreadPool;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use wordfun::{
+    Anagram, Dictionary, Entry, FindWord, Lexicon, Matches, ParseError, SearchKey, Source, Tag,
+    Tags, Thesaurus, WordType, MAX_QUERY_LENGTH,
+};
+
+/// The kinds of advanced search
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Mode {
+    /// Words made from the letters in `fodder`
+    Anagram,
+    /// Words matching `pattern`
+    FindWord,
+    /// Synonyms of `fodder`
+    Thesaurus,
+}
+
+/// Request type for an advanced search, which is posted as JSON. For example:
+///
+/// ```json
+/// { "mode": "anagram", "fodder": "ailing raven", "enumeration": "5,6",
+///   "filters": { "categories": ["location"] }, "sort": "length", "limit": 20 }
+/// ```
+#[derive(Deserialize)]
+pub(super) struct SearchRequest {
+    mode: Mode,
+    /// A find-word pattern such as "h.r./...l../e.g". This is the query in find-word mode; in
+    /// the other modes, it narrows down the results, as when you know some crossing letters.
+    pattern: Option<String>,
+    /// The letters to anagram in anagram mode, or the word to look up in thesaurus mode.
+    fodder: Option<String>,
+    /// The lengths of the words answers must have, such as "4,5" or "4-5".
+    enumeration: Option<String>,
+    #[serde(default)]
+    filters: Filters,
+    #[serde(default)]
+    sort: SortOrder,
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+/// Restrictions on the answers to an advanced search. Each one that is empty allows anything.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Filters {
+    /// Parts of speech that answers could be, such as `["Noun", "Verb"]`.
+    pos: Vec<WordType>,
+    /// Categories that answers must have one of, such as `["location", "proper_noun"]`.
+    categories: Vec<Tag>,
+}
+
+/// The response for an advanced search. Every mode returns the same fields.
+#[derive(Serialize)]
+struct SearchResponse {
+    /// The query as we understood it
+    key: SearchKey,
+    #[serde(flatten)]
+    results: FullResults,
+}
+
+/// Serve an advanced search
+pub(super) async fn search(
+    request: web::Json<SearchRequest>,
+    r: web::Data<Reference>,
+) -> Result<HttpResponse, BadQuery> {
+    let (key, words) = find_words(
+        &request,
+        r.lexicon(),
+        r.dictionary(),
+        r.thesaurus(),
+        r.search_pool(),
+    )?;
+    let words = words.iter().map(|word| word.as_ref());
+    Ok(HttpResponse::Ok().json(SearchResponse {
+        key,
+        results: full_page(words, request.sort, request.offset, request.limit, &r),
+    }))
+}
+
+/// Finds the words that match an advanced search, in no particular order.
+fn find_words<'a>(
+    request: &SearchRequest,
+    lexicon: &'a Lexicon,
+    dict: &Dictionary,
+    thesaurus: &'a Thesaurus,
+    pool: Option<&ThreadPool>,
+) -> Result<(SearchKey, Vec<Cow<'a, str>>), BadQuery> {
+    let tags: Option<Tags> = Some(request.filters.categories.iter().copied().collect())
+        .filter(|tags: &Tags| !tags.is_empty());
+    let enumeration = request
+        .enumeration
+        .as_deref()
+        .map(parse_enumeration)
+        .transpose()?;
+    let fodder = request.fodder.as_deref().unwrap_or("");
+    // In find-word mode, the pattern is the query, so there's nothing left to narrow down.
+    let pattern = match request.mode {
+        Mode::FindWord => None,
+        _ => parse_pattern(request.pattern.as_deref())?,
+    };
+
+    let (mut key, mut words): (SearchKey, Vec<Cow<str>>) = match request.mode {
+        Mode::Anagram => {
+            let mut results = super::search(lexicon, Anagram::parse(fodder)?, tags, pool);
+            let words = results.by_ref().map(Cow::Borrowed).collect();
+            (results.key, words)
+        }
+        Mode::FindWord => {
+            let query = request.pattern.as_deref().unwrap_or("");
+            let mut results = super::search(lexicon, FindWord::parse(query)?, tags, pool);
+            let words = results.by_ref().map(Cow::Borrowed).collect();
+            (results.key, words)
+        }
+        Mode::Thesaurus => {
+            if fodder.trim().is_empty() {
+                return Err(ParseError::Empty.into());
+            }
+            let mut words: Vec<Cow<str>> = thesaurus
+                .expand(fodder, Source::DEFAULT, 1, lexicon)
+                .into_iter()
+                .map(|e| match e.synonym.word {
+                    Cow::Borrowed(word) => Cow::Borrowed(word.term.as_str()),
+                    Cow::Owned(word) => Cow::Owned(word.term),
+                })
+                .collect();
+            if let Some(tags) = tags {
+                words.retain(|word| lexicon.word_tags(word).intersects(tags));
+            }
+            let key = SearchKey {
+                search_string: fodder.to_string(),
+                len: String::new(),
+            };
+            (key, words)
+        }
+    };
+
+    words.retain(|word| {
+        let entry = Entry::from(word.as_ref());
+        pattern.as_ref().map_or(true, |p| p.matches(&entry))
+            && enumeration
+                .as_ref()
+                .map_or(true, |e| *e == entry.enumeration())
+            && (request.filters.pos.is_empty() || has_pos(word, &request.filters.pos, dict))
+    });
+    if let Some(enumeration) = enumeration {
+        key.len = enumeration;
+    }
+    Ok((key, words))
+}
+
+/// Parses an enumeration such as "4,5" or "4-5" into the form that [`Entry::enumeration`] uses.
+fn parse_enumeration(text: &str) -> Result<String, ParseError> {
+    let mut lengths = vec![0usize];
+    for (position, ch) in text.chars().enumerate() {
+        if let Some(digit) = ch.to_digit(10) {
+            let length = lengths.last_mut().unwrap();
+            *length = length.saturating_mul(10).saturating_add(digit as usize);
+        } else if matches!(ch, ',' | '-' | ' ') {
+            lengths.push(0);
+        } else {
+            return Err(ParseError::UnexpectedCharacter {
+                character: ch,
+                position,
+            });
+        }
+    }
+    lengths.retain(|&length| length > 0);
+    let total = lengths
+        .iter()
+        .fold(0, |sum: usize, &n| sum.saturating_add(n));
+    if total == 0 {
+        return Err(ParseError::Empty);
+    } else if total > MAX_QUERY_LENGTH {
+        return Err(ParseError::TooLong {
+            length: total,
+            max: MAX_QUERY_LENGTH,
+        });
+    }
+    Ok(lengths
+        .iter()
+        .map(|length| length.to_string())
+        .collect::<Vec<_>>()
+        .join(","))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    struct Data {
+        lexicon: Lexicon,
+        dict: Dictionary,
+        thesaurus: Thesaurus,
+    }
+
+    fn data() -> Data {
+        let mut lexicon = Lexicon::new(
+            vec![
+                "Aden",
+                "dean",
+                "Dane",
+                "node",
+                "done",
+                "town",
+                "Paris",
+                "metropolis",
+            ]
+            .into_iter(),
+        );
+        lexicon.tag(vec![("aden", Tag::Location), ("paris", Tag::Location)]);
+        let mut dict = Dictionary::new();
+        dict.insert("dean", "a college official", WordType::Noun);
+        dict.insert("done", "having finished", WordType::Adj);
+        dict.insert("node", "a connecting point", WordType::Noun);
+        let mut thesaurus = Thesaurus::new();
+        thesaurus.push(&["city", "town", "metropolis", "Paris"]);
+        Data {
+            lexicon,
+            dict,
+            thesaurus,
+        }
+    }
+
+    fn find(data: &Data, request: serde_json::Value) -> (SearchKey, Vec<String>) {
+        let request: SearchRequest = serde_json::from_value(request).unwrap();
+        let (key, words) =
+            find_words(&request, &data.lexicon, &data.dict, &data.thesaurus, None).unwrap();
+        let mut words: Vec<String> = words.into_iter().map(Cow::into_owned).collect();
+        words.sort();
+        (key, words)
+    }
+
+    #[test]
+    pub fn anagram_mode() {
+        let data = data();
+        let (key, words) = find(&data, json!({ "mode": "anagram", "fodder": "and e" }));
+        assert_eq!(key.search_string, "and e");
+        assert_eq!(words, vec!["Aden", "Dane", "dean"]);
+
+        let request = json!({ "mode": "anagram", "fodder": "and e", "filters": { "categories": ["location"] } });
+        assert_eq!(find(&data, request).1, vec!["Aden"]);
+        let request =
+            json!({ "mode": "anagram", "fodder": "and e", "filters": { "pos": ["Noun"] } });
+        assert_eq!(find(&data, request).1, vec!["dean"]);
+    }
+
+    #[test]
+    pub fn find_word_mode() {
+        let data = data();
+        let (key, words) = find(&data, json!({ "mode": "find_word", "pattern": "..n." }));
+        assert_eq!(key.search_string, "..n.");
+        assert_eq!(words, vec!["Dane", "done"]);
+
+        let request =
+            json!({ "mode": "find_word", "pattern": "..n.", "filters": { "pos": ["Adj"] } });
+        assert_eq!(find(&data, request).1, vec!["done"]);
+    }
+
+    #[test]
+    pub fn thesaurus_mode() {
+        let data = data();
+        let (key, words) = find(&data, json!({ "mode": "thesaurus", "fodder": "city" }));
+        assert_eq!(key.search_string, "city");
+        assert_eq!(words, vec!["Paris", "city", "metropolis", "town"]);
+
+        let request = json!({ "mode": "thesaurus", "fodder": "city", "filters": { "categories": ["location"] } });
+        assert_eq!(find(&data, request).1, vec!["Paris"]);
+        let request = json!({ "mode": "thesaurus", "fodder": "city", "enumeration": "4" });
+        let (key, words) = find(&data, request);
+        assert_eq!(key.len, "4");
+        assert_eq!(words, vec!["city", "town"]);
+        let request = json!({ "mode": "thesaurus", "fodder": "city", "pattern": "p...." });
+        assert_eq!(find(&data, request).1, vec!["Paris"]);
+    }
+
+    #[test]
+    pub fn parse_enumerations() {
+        assert_eq!(parse_enumeration("4,5").unwrap(), "4,5");
+        assert_eq!(parse_enumeration("4-5").unwrap(), "4,5");
+        assert_eq!(parse_enumeration(" 10 ").unwrap(), "10");
+        assert_eq!(parse_enumeration(",").err(), Some(ParseError::Empty));
+        assert_eq!(
+            parse_enumeration("4,x").err(),
+            Some(ParseError::UnexpectedCharacter {
+                character: 'x',
+                position: 2
+            })
+        );
+        assert!(parse_enumeration("99999999999999999999999").is_err());
+    }
+}
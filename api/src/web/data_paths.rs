@@ -0,0 +1,152 @@
+use super::refdata::read_to_string;
+use crate::DataOptions;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// The data directory used when neither the flags nor the config file give one.
+const DEFAULT_DATA_DIR: &str = "data";
+
+/// The data paths that can be set in a config file, as in:
+///
+/// ```toml
+/// data_dir = "/var/lib/wordfun"
+/// wordnet_dir = "/usr/share/wordnet/dict"
+/// ```
+#[derive(Deserialize, Default, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct DataConfig {
+    data_dir: Option<PathBuf>,
+    lexicon: Option<PathBuf>,
+    popular_words: Option<PathBuf>,
+    thesaurus: Option<PathBuf>,
+    wordnet_dir: Option<PathBuf>,
+    index: Option<PathBuf>,
+}
+
+/// Where each data source is, after combining the flags, the config file and the defaults.
+#[derive(Debug, PartialEq)]
+pub(super) struct DataPaths {
+    pub lexicon: PathBuf,
+    pub popular_words: PathBuf,
+    /// `None` means the bundled thesaurus.
+    pub thesaurus: Option<PathBuf>,
+    /// `None` means the bundled Wordnet.
+    pub wordnet_dir: Option<PathBuf>,
+    pub index: PathBuf,
+}
+
+impl DataPaths {
+    pub fn new(options: &DataOptions) -> Result<Self> {
+        let config = match &options.config {
+            Some(path) => DataConfig::open(path)?,
+            None => DataConfig::default(),
+        };
+        Ok(Self::resolve(options, config))
+    }
+
+    /// Takes each path from the flags if it's there, or else from the config file, or else from
+    /// the data directory.
+    fn resolve(options: &DataOptions, config: DataConfig) -> Self {
+        let data_dir = options
+            .data_dir
+            .clone()
+            .or(config.data_dir)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DATA_DIR));
+        let in_data_dir =
+            |flag: &Option<PathBuf>, setting: Option<PathBuf>, name: &str| -> PathBuf {
+                flag.clone()
+                    .or(setting)
+                    .unwrap_or_else(|| data_dir.join(name))
+            };
+        Self {
+            lexicon: in_data_dir(&options.lexicon, config.lexicon, "lexicon.txt"),
+            popular_words: in_data_dir(
+                &options.popular_words,
+                config.popular_words,
+                "popular_words.txt",
+            ),
+            index: in_data_dir(&options.index, config.index, "wordfun.index"),
+            thesaurus: options.thesaurus.clone().or(config.thesaurus),
+            wordnet_dir: options.wordnet_dir.clone().or(config.wordnet_dir),
+        }
+    }
+}
+
+impl DataConfig {
+    /// Reads a config file. Relative paths in it are taken relative to its directory, so that
+    /// the file works wherever the server is started from.
+    fn open(path: &Path) -> Result<Self> {
+        let text = read_to_string(path)?;
+        let config = Self::parse(&text)
+            .with_context(|| format!("Could not parse config file {:?}", path))?;
+        Ok(config.relative_to(path.parent().unwrap_or_else(|| Path::new(""))))
+    }
+
+    fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(text)
+    }
+
+    fn relative_to(self, dir: &Path) -> Self {
+        let join = |path: Option<PathBuf>| path.map(|path| dir.join(path));
+        Self {
+            data_dir: join(self.data_dir),
+            lexicon: join(self.lexicon),
+            popular_words: join(self.popular_words),
+            thesaurus: join(self.thesaurus),
+            wordnet_dir: join(self.wordnet_dir),
+            index: join(self.index),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use structopt::StructOpt;
+
+    fn options(args: &[&str]) -> DataOptions {
+        DataOptions::from_iter(std::iter::once("wordfun").chain(args.iter().copied()))
+    }
+
+    #[test]
+    pub fn flags_override_config() {
+        let config = DataConfig::parse(
+            r#"
+            data_dir = "/srv/wordfun"
+            lexicon = "/srv/words.txt"
+            wordnet_dir = "/usr/share/wordnet"
+            "#,
+        )
+        .unwrap();
+        let paths = DataPaths::resolve(&options(&["--lexicon", "mine.txt"]), config);
+        assert_eq!(
+            paths,
+            DataPaths {
+                lexicon: PathBuf::from("mine.txt"),
+                popular_words: PathBuf::from("/srv/wordfun/popular_words.txt"),
+                thesaurus: None,
+                wordnet_dir: Some(PathBuf::from("/usr/share/wordnet")),
+                index: PathBuf::from("/srv/wordfun/wordfun.index"),
+            }
+        );
+
+        let paths = DataPaths::resolve(&options(&[]), DataConfig::default());
+        assert_eq!(paths.lexicon, PathBuf::from("data/lexicon.txt"));
+        assert_eq!(paths.index, PathBuf::from("data/wordfun.index"));
+    }
+
+    #[test]
+    pub fn config_paths_are_relative_to_the_file() {
+        let config = DataConfig::parse("thesaurus = \"synonyms.txt\"\nindex = \"/tmp/x.index\"")
+            .unwrap()
+            .relative_to(Path::new("/etc/wordfun"));
+        assert_eq!(
+            config.thesaurus,
+            Some(PathBuf::from("/etc/wordfun/synonyms.txt"))
+        );
+        assert_eq!(config.index, Some(PathBuf::from("/tmp/x.index")));
+        assert!(DataConfig::parse("lexikon = \"words.txt\"").is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use serde::Serialize;
-use wordfun::{Popularity, Ranked, SearchKey};
+use wordfun::{Popularity, Ranked, Results};
 
 /// Builder for preview responses.
 pub struct Preview<'a> {
@@ -9,7 +9,7 @@ pub struct Preview<'a> {
 }
 
 /// Data format of a response to a preview request.  The preview trims the result set down to a
-/// manageable size, and moves the most common English words to the front.
+/// manageable size, and sorts the words by popularity.
 #[derive(Serialize)]
 pub struct PreviewResponse {
     pub full_count: usize,
@@ -35,17 +35,19 @@ impl<'a> Preview<'a> {
         }
     }
 
-    pub fn build(&self, key: &SearchKey, words: &[&str]) -> PreviewResponse {
-        let mut words: Vec<_> = words.iter().map(|w| self.popularity.to_ranked(w)).collect();
-        words.sort();
-        let full_count = words.len();
-        words.truncate(self.display_count(words.len()));
+    /// Builds a preview of the most popular results. Only the words that might be shown are
+    /// kept, so this is cheap even when there are lots of results.
+    pub fn build(&self, mut results: Results<'_>) -> PreviewResponse {
+        let most = self.max_results + self.wiggle;
+        let (mut words, full_count) = self.popularity.top(results.by_ref(), most);
+        words.truncate(self.display_count(full_count));
+        let key = &results.key;
 
         let words: Vec<_> = words
             .into_iter()
             .map(|w| match w {
                 Ranked::Ranked(text, _) => (text.to_string(), true),
-                Ranked::Unranked(text) => (text.to_string(), false),
+                Ranked::Scored(text, _) | Ranked::Unranked(text) => (text.to_string(), false),
             })
             .map(|(text, ranked)| PreviewWord { text, ranked })
             .collect();
@@ -91,14 +93,9 @@ mod test {
     use wordfun::Lexicon;
 
     // This does roughly what the UI is going to do.
-    pub fn format_preview(
-        search: &SearchKey,
-        results: &[&str],
-        max_count: usize,
-        wiggle: usize,
-    ) -> String {
+    pub fn format_preview(results: Results<'_>, max_count: usize, wiggle: usize) -> String {
         let popularity = Popularity::default();
-        let preview = Preview::new(max_count, wiggle, &popularity).build(search, results);
+        let preview = Preview::new(max_count, wiggle, &popularity).build(results);
         // rats (4): 5 matches (arts, rats, star, tars, tsar)
         let search = format!("{} ({})", preview.query, preview.lengths);
 
@@ -126,21 +123,21 @@ mod test {
     #[test]
     pub fn test_format_preview() {
         let lex = Lexicon::new(vec!["abc", "def", "ghij"].into_iter());
-        let results = lex.find_word("...");
+        let results = lex.find_word("...").unwrap();
 
         assert_eq!(
             "... (3): 2 matches (abc, def)",
-            format_preview(&results.key, &results.words, 10, 0)
+            format_preview(results, 10, 0)
         )
     }
 
     #[test]
     pub fn format_preview_two_words() {
         let lex = Lexicon::new(vec!["oneword", "two words", "three whole words"].into_iter());
-        let results = lex.find_word(".../W.R..");
+        let results = lex.find_word(".../W.R..").unwrap();
         assert_eq!(
             ".../w.r.. (3,5): 1 match (two words)",
-            format_preview(&results.key, &results.words, 10, 0)
+            format_preview(results, 10, 0)
         )
     }
 
@@ -148,10 +145,11 @@ mod test {
     pub fn format_preview_max_len_results() {
         let lex = Lexicon::new(vec!["aaa", "aab", "aac"].into_iter());
 
-        let results = lex.find_word("...");
+        let results = lex.find_word("...").unwrap();
+
         assert_eq!(
             "... (3): 3 matches (aaa, aab, aac)",
-            format_preview(&results.key, &results.words, 3, 0)
+            format_preview(results, 3, 0)
         )
     }
 
@@ -162,8 +160,9 @@ mod test {
             let words = vec!["aaa", "aab", "aac", "aad", "aae", "aaf", "aag", "aah"];
             let lex = Lexicon::new(words.into_iter());
 
-            let results = lex.find_word(search);
-            format_preview(&results.key, &results.words, max, wiggle)
+            let results = lex.find_word(search).unwrap();
+
+            format_preview(results, max, wiggle)
         }
 
         #[test]
@@ -1,18 +1,26 @@
 use anyhow::{Context, Result};
+use rayon::{ThreadPool, ThreadPoolBuilder};
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use super::data_paths::DataPaths;
 use crate::Options;
-use wordfun::{Dictionary, Lexicon, Popularity, Thesaurus};
+use wordfun::{
+    DataSource, DataSources, Dictionary, FrequencyList, Glossary, Lexicon, PastAnswers, Popularity,
+    Precedence, ReverseDictionary, Snapshot, SnapshotError, Thesaurus, Wordnet,
+};
 
 struct Inner {
     lex: Lexicon,
     thesaurus: Thesaurus,
     dictionary: Dictionary,
+    reverse_dictionary: ReverseDictionary,
     popularity: Popularity,
+    past_answers: PastAnswers,
     assets_dir: Option<PathBuf>,
+    search_pool: Option<ThreadPool>,
 }
 
 #[derive(Clone)]
@@ -20,28 +28,162 @@ pub struct Reference {
     inner: Arc<Inner>,
 }
 
-fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
+pub(super) fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
     let p = path.as_ref();
     fs::read_to_string(p).with_context(|| format!("Could not read file {:?}", p))
 }
 
+/// The contents of the data files, read once so that the text that's hashed for the index is
+/// the text that's built from.
+struct DataFiles {
+    lexicon: String,
+    popular_words: String,
+    thesaurus: Option<String>,
+    wordnet: Wordnet,
+    sources: DataSources,
+}
+
+impl DataFiles {
+    fn read(paths: &DataPaths) -> Result<Self> {
+        let lexicon = read_to_string(&paths.lexicon)?;
+        let popular_words = read_to_string(&paths.popular_words)?;
+        let thesaurus = match &paths.thesaurus {
+            Some(path) => Some(read_to_string(path)?),
+            None => None,
+        };
+        let wordnet = match &paths.wordnet_dir {
+            Some(dir) => Wordnet::open(dir)
+                .with_context(|| format!("Could not load Wordnet from {:?}", dir))?,
+            None => Wordnet::bundled(),
+        };
+        let sources = DataSources {
+            lexicon: DataSource::new(Some(paths.lexicon.clone()), &lexicon),
+            popular_words: DataSource::new(Some(paths.popular_words.clone()), &popular_words),
+            thesaurus: match &thesaurus {
+                Some(text) => DataSource::new(paths.thesaurus.clone(), text),
+                None => DataSource::bundled_thesaurus(),
+            },
+            wordnet: DataSource::wordnet(paths.wordnet_dir.clone(), &wordnet),
+        };
+        Ok(Self {
+            lexicon,
+            popular_words,
+            thesaurus,
+            wordnet,
+            sources,
+        })
+    }
+
+    /// Builds the reference data from the data files, after checking that Wordnet parses.
+    fn build(self) -> Result<Snapshot> {
+        self.wordnet.check().context("Could not load Wordnet")?;
+        Ok(Snapshot::build(
+            &self.lexicon,
+            &self.popular_words,
+            self.thesaurus.as_deref(),
+            &self.wordnet,
+            self.sources,
+        ))
+    }
+}
+
+/// Loads the reference data from the index if it was built from the same data files, or else
+/// from the files themselves.
+fn load_snapshot(paths: &DataPaths) -> Result<Snapshot> {
+    let files = DataFiles::read(paths)?;
+    let index = &paths.index;
+    if index.exists() {
+        match Snapshot::open_built_from(index, &files.sources) {
+            Ok(snapshot) => {
+                log::info!("Loaded index {:?}", index);
+                return Ok(snapshot);
+            }
+            Err(e @ SnapshotError::OtherSources { .. }) => log::warn!(
+                "Ignoring index {:?}: {}. Run build-index to update it.",
+                index,
+                e
+            ),
+            Err(e) => log::warn!("Ignoring index {:?}: {}", index, e),
+        }
+    }
+    files.build()
+}
+
+/// Builds the reference data from the data files and saves it as an index.
+pub fn build_index(options: &Options) -> Result<()> {
+    let paths = DataPaths::new(&options.data)?;
+    let snapshot = DataFiles::read(&paths)?.build()?;
+    snapshot
+        .save(&paths.index)
+        .with_context(|| format!("Could not save index {:?}", paths.index))?;
+    log::info!("Wrote index {:?}", paths.index);
+    Ok(())
+}
+
 impl Reference {
     pub fn new(options: &Options) -> Result<Self> {
-        let lex_text = read_to_string("data/lexicon.txt")?;
-        let popular_words = read_to_string("data/popular_words.txt")?;
-
-        let lex = Lexicon::new(lex_text.lines());
-        let thesaurus = Thesaurus::init();
+        let paths = DataPaths::new(&options.data)?;
+        let Snapshot {
+            sources: _,
+            lexicon: lex,
+            mut dictionary,
+            mut reverse_dictionary,
+            thesaurus,
+            mut popularity,
+        } = load_snapshot(&paths)?;
+        log::info!(
+            "Loaded {} lexicon entries in {} KiB",
+            lex.len(),
+            lex.heap_size() / 1024
+        );
         let assets_dir = options.assets_dir.clone();
-        let popularity = Popularity::from(popular_words.lines());
-        let dictionary = Dictionary::from_wordnet();
-
+        for list in &options.frequency_lists {
+            let frequencies = FrequencyList::open(&list.path)
+                .with_context(|| format!("Could not load frequency list {:?}", list.path))?;
+            popularity.add_list(frequencies, list.weight);
+        }
+        let past_answers = match &options.past_answers {
+            Some(path) => PastAnswers::open(path)
+                .with_context(|| format!("Could not load past answers {:?}", path))?,
+            None => PastAnswers::new(),
+        };
+        if !past_answers.is_empty() {
+            popularity.add_list(past_answers.frequency_list(), 1.0);
+        }
+        // Add the glossaries in reverse so that the first one ends up with the highest precedence.
+        for path in options.glossaries.iter().rev() {
+            let glossary = Glossary::open(path)
+                .with_context(|| format!("Could not load glossary {:?}", path))?;
+            dictionary.add_source(&glossary, Precedence::Before);
+        }
+        // A glossary's headwords change how every definition is stemmed, so the reverse
+        // dictionary from the index can't just be extended.
+        if !options.glossaries.is_empty() {
+            reverse_dictionary = ReverseDictionary::new(&dictionary);
+        }
+        let search_pool = if options.search_threads == 1 {
+            None
+        } else {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(options.search_threads)
+                .thread_name(|i| format!("search-{}", i))
+                .build()
+                .context("Could not start the search threads")?;
+            log::info!(
+                "Splitting searches across {} threads",
+                pool.current_num_threads()
+            );
+            Some(pool)
+        };
         let inner = Inner {
             lex,
             thesaurus,
             assets_dir,
             popularity,
+            past_answers,
             dictionary,
+            reverse_dictionary,
+            search_pool,
         };
         Ok(Self {
             inner: Arc::new(inner),
@@ -56,6 +198,10 @@ impl Reference {
         &self.inner.dictionary
     }
 
+    pub fn reverse_dictionary(&self) -> &ReverseDictionary {
+        &self.inner.reverse_dictionary
+    }
+
     pub fn thesaurus(&self) -> &Thesaurus {
         &self.inner.thesaurus
     }
@@ -67,4 +213,88 @@ impl Reference {
     pub fn popularity(&self) -> &Popularity {
         &self.inner.popularity
     }
+
+    pub fn past_answers(&self) -> &PastAnswers {
+        &self.inner.past_answers
+    }
+
+    /// The threads that anagram and find-word searches are split across, or `None` if they run
+    /// on the request's own thread.
+    pub fn search_pool(&self) -> Option<&ThreadPool> {
+        self.inner.search_pool.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Writes a small set of data files, with an empty Wordnet, to a new directory.
+    fn data_dir(name: &str) -> DataPaths {
+        let dir = std::env::temp_dir().join(format!("wordfun-{}-{}", name, std::process::id()));
+        let wordnet_dir = dir.join("wordnet");
+        fs::create_dir_all(&wordnet_dir).unwrap();
+        for file in &[
+            "index.sense",
+            "cntlist.rev",
+            "data.noun",
+            "data.verb",
+            "data.adj",
+            "data.adv",
+        ] {
+            fs::write(wordnet_dir.join(file), "").unwrap();
+        }
+        fs::write(dir.join("lexicon.txt"), "cafe\nbistro\n").unwrap();
+        fs::write(dir.join("popular_words.txt"), "cafe\n").unwrap();
+        fs::write(dir.join("thesaurus.txt"), "cafe,bistro\n").unwrap();
+        DataPaths {
+            lexicon: dir.join("lexicon.txt"),
+            popular_words: dir.join("popular_words.txt"),
+            thesaurus: Some(dir.join("thesaurus.txt")),
+            wordnet_dir: Some(wordnet_dir),
+            index: dir.join("wordfun.index"),
+        }
+    }
+
+    #[test]
+    pub fn ignore_index_from_other_data() {
+        let paths = data_dir("sources");
+        DataFiles::read(&paths)
+            .unwrap()
+            .build()
+            .unwrap()
+            .save(&paths.index)
+            .unwrap();
+        assert_eq!(load_snapshot(&paths).unwrap().lexicon.len(), 2);
+
+        fs::write(&paths.lexicon, "cafe\nbistro\nbrasserie\n").unwrap();
+        let files = DataFiles::read(&paths).unwrap();
+        assert!(matches!(
+            Snapshot::open_built_from(&paths.index, &files.sources),
+            Err(SnapshotError::OtherSources { .. })
+        ));
+        assert_eq!(load_snapshot(&paths).unwrap().lexicon.len(), 3);
+
+        fs::write(&paths.lexicon, "cafe\nbistro\n").unwrap();
+        let bundled = DataPaths {
+            thesaurus: None,
+            ..paths
+        };
+        let files = DataFiles::read(&bundled).unwrap();
+        assert!(matches!(
+            Snapshot::open_built_from(&bundled.index, &files.sources),
+            Err(SnapshotError::OtherSources { .. })
+        ));
+        fs::remove_dir_all(bundled.index.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    pub fn check_wordnet_when_building() {
+        let paths = data_dir("senses");
+        let index_sense = paths.wordnet_dir.as_ref().unwrap().join("index.sense");
+        fs::write(&index_sense, "dog%1:05:00:: lots 1 42\n").unwrap();
+        let error = load_snapshot(&paths).err().unwrap();
+        assert!(format!("{:#}", error).contains("Could not parse line 1"));
+        fs::remove_dir_all(paths.index.parent().unwrap()).unwrap();
+    }
 }
@@ -0,0 +1,183 @@
+use super::refdata::Reference;
+use super::{full_match, parse_categories, parse_pos, retain_pos, search, BadQuery};
+
+use actix_web::error::BlockingError;
+use actix_web::web::{self, Bytes};
+use actix_web::HttpResponse;
+use futures::channel::mpsc;
+use futures::executor::block_on;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use wordfun::{Anagram, FindWord, Matches, SearchKey};
+
+/// Request type for a streamed anagram or find-word search
+#[derive(Deserialize)]
+pub(super) struct StreamQuery {
+    q: String,
+    pos: Option<String>,
+    category: Option<String>,
+    /// How to send the matches. The default is NDJSON.
+    #[serde(default)]
+    format: StreamFormat,
+}
+
+/// The formats that matches can be streamed in
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum StreamFormat {
+    /// One JSON `Match` per line
+    Ndjson,
+    /// Server-sent events: a `match` event for each word, then a `done` event with the total,
+    /// so that an `EventSource` knows to close instead of reconnecting
+    Sse,
+}
+
+impl Default for StreamFormat {
+    fn default() -> Self {
+        StreamFormat::Ndjson
+    }
+}
+
+/// The data in the `done` event at the end of a server-sent event stream
+#[derive(Serialize)]
+struct Done {
+    total: usize,
+}
+
+/// How many matches can be waiting to be sent before the search pauses for the client to
+/// catch up.
+const STREAM_BUFFER: usize = 64;
+
+/// Stream anagram matches as they are found
+pub(super) async fn stream_an(
+    query: web::Query<StreamQuery>,
+    r: web::Data<Reference>,
+) -> Result<HttpResponse, BadQuery> {
+    // Parse before streaming, so that a bad query still gets a 400.
+    let pattern = Anagram::parse(&query.q)?;
+    stream(pattern, query.into_inner(), r)
+}
+
+/// Stream find-word matches as they are found
+pub(super) async fn stream_fw(
+    query: web::Query<StreamQuery>,
+    r: web::Data<Reference>,
+) -> Result<HttpResponse, BadQuery> {
+    let pattern = FindWord::parse(&query.q)?;
+    stream(pattern, query.into_inner(), r)
+}
+
+/// Why a stream ended before the search finished
+#[derive(Debug)]
+enum Ended {
+    /// The client went away
+    Disconnected,
+    /// A match couldn't be written as JSON
+    Serialize(serde_json::Error),
+}
+
+/// Searches the lexicon on actix's blocking thread pool, sending each match to the client as
+/// soon as it's found. The matches come in lexicon order, not by popularity, since sorting
+/// would mean waiting for them all.
+///
+/// If the client disconnects, the response stream is dropped, so the next send fails and the
+/// search stops. If a match can't be serialized, the stream ends there.
+fn stream<M>(
+    pattern: M,
+    query: StreamQuery,
+    r: web::Data<Reference>,
+) -> Result<HttpResponse, BadQuery>
+where
+    M: Matches + Into<SearchKey> + Clone + Send + Sync + 'static,
+{
+    let format = query.format;
+    let tags = parse_categories(query.category.as_deref())?;
+    let pos = parse_pos(query.pos.as_deref())?;
+    let (mut tx, rx) = mpsc::channel(STREAM_BUFFER);
+    let search = move || {
+        let results = search(r.lexicon(), pattern, tags, None);
+        let mut total = 0;
+        for word in retain_pos(results, pos, r.dictionary()) {
+            total += 1;
+            send(&mut tx, format, "match", &full_match(word, &r))?;
+        }
+        if format == StreamFormat::Sse {
+            send(&mut tx, format, "done", &Done { total })?;
+        }
+        Ok(())
+    };
+    // The blocking pool only starts the search once the future is polled, so it has to be
+    // spawned rather than dropped.
+    actix_rt::spawn(async move {
+        if let Err(BlockingError::Error(Ended::Serialize(e))) = web::block(search).await {
+            log::error!("Stopped streaming matches: {}", e);
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .header("Cache-Control", "no-cache")
+        .streaming(rx.map(Ok::<Bytes, actix_web::Error>)))
+}
+
+/// Sends a value to the client as an event.
+fn send<T: Serialize>(
+    tx: &mut mpsc::Sender<Bytes>,
+    format: StreamFormat,
+    name: &str,
+    value: &T,
+) -> Result<(), Ended> {
+    let json = serde_json::to_string(value).map_err(Ended::Serialize)?;
+    block_on(tx.send(format.event(name, &json))).map_err(|_| Ended::Disconnected)
+}
+
+impl StreamFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            StreamFormat::Ndjson => "application/x-ndjson",
+            StreamFormat::Sse => "text/event-stream",
+        }
+    }
+
+    /// Frames some JSON as an event. NDJSON has no event names, so it's just the line.
+    fn event(self, name: &str, json: &str) -> Bytes {
+        match self {
+            StreamFormat::Ndjson => Bytes::from(format!("{}\n", json)),
+            StreamFormat::Sse => Bytes::from(format!("event: {}\ndata: {}\n\n", name, json)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn frame_events() {
+        assert_eq!(
+            StreamFormat::Ndjson.event("match", r#"{"word":"erne"}"#),
+            Bytes::from("{\"word\":\"erne\"}\n")
+        );
+        assert_eq!(
+            StreamFormat::Sse.event("done", r#"{"total":1}"#),
+            Bytes::from("event: done\ndata: {\"total\":1}\n\n")
+        );
+    }
+
+    #[test]
+    pub fn end_stream_on_errors() {
+        let (mut tx, mut rx) = mpsc::channel(STREAM_BUFFER);
+        send(&mut tx, StreamFormat::Ndjson, "done", &Done { total: 1 }).unwrap();
+        assert_eq!(block_on(rx.next()), Some(Bytes::from("{\"total\":1}\n")));
+
+        // JSON object keys have to be strings.
+        let unserializable: std::collections::HashMap<(u8, u8), u8> =
+            vec![((1, 2), 3)].into_iter().collect();
+        let result = send(&mut tx, StreamFormat::Ndjson, "match", &unserializable);
+        assert!(matches!(result, Err(Ended::Serialize(_))));
+
+        drop(rx);
+        let result = send(&mut tx, StreamFormat::Ndjson, "done", &Done { total: 1 });
+        assert!(matches!(result, Err(Ended::Disconnected)));
+    }
+}
@@ -1,11 +1,15 @@
 use deunicode::deunicode;
-use fixedbitset::FixedBitSet;
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Clone)]
-pub struct WordBreaks(FixedBitSet);
+/// The letter positions of the word breaks in a phrase, as a bitmask. Positions past
+/// [`SIZE`](Self::SIZE) aren't recorded, which only matters for phrases longer than any
+/// query can be.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct WordBreaks(u64);
 
 impl WordBreaks {
-    const SIZE: usize = 60;
+    /// The most positions we have room for.
+    pub const SIZE: usize = 60;
 
     pub fn for_word(word: &str) -> Self {
         let mut breaks = Self::default();
@@ -13,7 +17,7 @@ impl WordBreaks {
         let mut pos = 0;
         for ch in deunicode(word).chars() {
             if was_letter && !ch.is_ascii_alphabetic() {
-                breaks.0.insert(pos);
+                breaks.insert(pos);
             }
             if ch.is_ascii_alphabetic() {
                 pos += 1;
@@ -25,23 +29,31 @@ impl WordBreaks {
         breaks
     }
 
-    pub fn positions(&self) -> impl Iterator<Item = usize> + '_ {
-        self.0.ones()
+    pub fn positions(&self) -> impl Iterator<Item = usize> {
+        let bits = self.0;
+        (0..Self::SIZE).filter(move |pos| bits & (1 << pos) != 0)
     }
 
     pub fn from_positions(positions: &[usize]) -> Self {
-        let vec: Vec<usize> = positions.into();
-        let mut bitset = FixedBitSet::with_capacity(Self::SIZE);
-        bitset.extend(vec);
-        Self(bitset)
+        let mut breaks = Self::default();
+        for &pos in positions {
+            breaks.insert(pos);
+        }
+        breaks
+    }
+
+    fn insert(&mut self, pos: usize) {
+        if pos < Self::SIZE {
+            self.0 |= 1 << pos;
+        }
     }
 
     pub fn is_superset(&self, other: &WordBreaks) -> bool {
-        self.0.is_superset(&other.0)
+        self.0 & other.0 == other.0
     }
 
     pub fn is_empty(&self) -> bool {
-        self.0.count_ones(..) == 0
+        self.0 == 0
     }
 
     pub fn to_vec(&self) -> Vec<usize> {
@@ -49,12 +61,6 @@ impl WordBreaks {
     }
 }
 
-impl Default for WordBreaks {
-    fn default() -> Self {
-        Self(FixedBitSet::with_capacity(Self::SIZE))
-    }
-}
-
 impl std::fmt::Debug for WordBreaks {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "WordBreaks({:?})", &self.to_vec())
@@ -75,4 +81,12 @@ mod test {
         let positions: Vec<usize> = WordBreaks::for_word("café au lait").positions().collect();
         assert_eq!(positions, &[4, 6]);
     }
+
+    #[test]
+    pub fn ignore_breaks_past_the_end() {
+        let breaks = WordBreaks::from_positions(&[3, 59, 60, 100]);
+        assert_eq!(breaks.to_vec(), vec![3, 59]);
+        assert!(breaks.is_superset(&WordBreaks::from_positions(&[59])));
+        assert!(!breaks.is_superset(&WordBreaks::from_positions(&[4])));
+    }
 }
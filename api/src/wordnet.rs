@@ -1,11 +1,13 @@
 mod import;
+mod inflection;
 mod stemming;
 mod word_type;
 
 use std::collections::HashMap;
 
-pub use import::from_wordnet;
-pub use stemming::{stem, StemmedWord};
+pub use import::{Relation, Synset, Wordnet, WordnetError};
+pub use inflection::{inflect_phrase, inflections, Inflected, Inflection};
+pub use stemming::{stem, stem_phrase, StemmedWord};
 pub use word_type::WordType;
 
 /// The type of the object contained in a save file.
@@ -1,55 +1,212 @@
 mod data;
+mod lexname;
 mod sense;
+mod synset;
 
 use itertools::Itertools;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
 use std::iter::FromIterator;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
 
-pub use data::lookup;
+pub use lexname::lexname;
 pub use sense::Sense;
+pub use synset::{Relation, Synset};
 
 pub use super::{DictionaryData, WordType};
 
-const INDEX_SENSE: &str = include_str!("data/index.sense");
-
-pub fn from_wordnet() -> DictionaryData {
-    let entries = INDEX_SENSE
-        .lines()
-        .map(|line| line.parse::<Sense>())
-        .take_while(std::result::Result::is_ok)
-        .map(std::result::Result::unwrap)
-        .group_by(|sense| sense.lemma.clone());
-
-    let mut map = HashMap::new();
-    for (lemma, senses) in entries.into_iter() {
-        let senses: SenseList = senses.collect();
-        // Sort the list so that the first sense for each word
-        // type will be the first found.
-        let mut best_definitions = Vec::new();
-        if let Some(sense) = senses.find(WordType::Noun) {
-            if let Some(defn) = lookup(sense) {
-                best_definitions.push((WordType::Noun, defn));
-            }
+/// The parts of speech, in the order their data files are read.
+const WORD_TYPES: [WordType; 4] = [WordType::Noun, WordType::Verb, WordType::Adj, WordType::Adv];
+
+#[derive(Debug, Error)]
+pub enum WordnetError {
+    #[error("Could not read {path:?}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("Could not parse line {line} of {path:?}")]
+    Parse {
+        path: PathBuf,
+        line: usize,
+        #[source]
+        source: sense::ParseError,
+    },
+}
+
+/// The Wordnet database files that definitions, categories, tag counts and synonyms come from.
+///
+/// Copies of the files are built into the binary, and [`bundled`](Self::bundled) uses those.
+/// To use another copy of Wordnet, such as a newer release, [`open`](Self::open) its `dict`
+/// directory.
+pub struct Wordnet {
+    /// The `dict` directory the files were read from, or `None` for the bundled copy.
+    dir: Option<PathBuf>,
+    index_sense: Cow<'static, str>,
+    cntlist_rev: Cow<'static, str>,
+    data_noun: Cow<'static, str>,
+    data_verb: Cow<'static, str>,
+    data_adj: Cow<'static, str>,
+    data_adv: Cow<'static, str>,
+}
+
+impl Wordnet {
+    /// The Wordnet files built into the binary.
+    pub fn bundled() -> Self {
+        Self {
+            dir: None,
+            index_sense: Cow::Borrowed(include_str!("data/index.sense")),
+            cntlist_rev: Cow::Borrowed(include_str!("data/cntlist.rev")),
+            data_noun: Cow::Borrowed(include_str!("data/data.noun")),
+            data_verb: Cow::Borrowed(include_str!("data/data.verb")),
+            data_adj: Cow::Borrowed(include_str!("data/data.adj")),
+            data_adv: Cow::Borrowed(include_str!("data/data.adv")),
         }
-        if let Some(sense) = senses.find(WordType::Verb) {
-            if let Some(defn) = lookup(sense) {
-                best_definitions.push((WordType::Verb, defn));
-            }
+    }
+
+    /// Reads `index.sense`, `cntlist.rev`, and the `data.*` files from a Wordnet `dict`
+    /// directory. The files aren't parsed until they're used, so call [`check`](Self::check)
+    /// before building anything from them.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self, WordnetError> {
+        let dir = dir.as_ref();
+        let read = |name: &str| {
+            let path = dir.join(name);
+            fs::read_to_string(&path)
+                .map(Cow::Owned)
+                .map_err(|source| WordnetError::Io { path, source })
+        };
+        Ok(Self {
+            dir: Some(dir.to_path_buf()),
+            index_sense: read("index.sense")?,
+            cntlist_rev: read("cntlist.rev")?,
+            data_noun: read("data.noun")?,
+            data_verb: read("data.verb")?,
+            data_adj: read("data.adj")?,
+            data_adv: read("data.adv")?,
+        })
+    }
+
+    /// Checks that every line of `index.sense` is a sense, reporting the first one that isn't.
+    /// The bundled copy is known to be good, so it isn't checked.
+    pub fn check(&self) -> Result<(), WordnetError> {
+        match &self.dir {
+            Some(dir) => check_senses(&dir.join("index.sense"), &self.index_sense),
+            None => Ok(()),
         }
-        if let Some(sense) = senses.find(WordType::Adj) {
-            if let Some(defn) = lookup(sense) {
-                best_definitions.push((WordType::Adj, defn));
-            }
+    }
+
+    /// Returns the first definition of each lemma for each part of speech.
+    pub(crate) fn definitions(&self) -> DictionaryData {
+        let entries = self.senses().group_by(|sense| sense.lemma.clone());
+
+        let mut map = HashMap::new();
+        for (lemma, senses) in entries.into_iter() {
+            let senses: SenseList = senses.collect();
+            // Sort the list so that the first sense for each word
+            // type will be the first found.
+            let best_definitions = WORD_TYPES
+                .iter()
+                .filter_map(|&word_type| {
+                    let sense = senses.find(word_type)?;
+                    Some((word_type, data::lookup(self.data(word_type), sense)?))
+                })
+                .collect();
+            map.insert(lemma, best_definitions);
         }
-        if let Some(sense) = senses.find(WordType::Adv) {
-            if let Some(defn) = lookup(sense) {
-                best_definitions.push((WordType::Adv, defn));
-            }
+
+        map
+    }
+
+    /// Returns the lexicographer file of every sense in Wordnet, as `(lemma, lexname)` pairs
+    /// such as `("aachen", "noun.location")`. A lemma with several senses can appear more than
+    /// once.
+    pub(crate) fn lexnames(&self) -> impl Iterator<Item = (String, &'static str)> + '_ {
+        self.senses()
+            .filter_map(|sense| Some((sense.lemma, lexname(sense.lex_filenum)?)))
+    }
+
+    /// Returns the number of times each sense was tagged in Wordnet's semantic concordance, as
+    /// `(lemma, count)` pairs. A lemma with several tagged senses appears once for each of them.
+    pub(crate) fn tag_counts(&self) -> impl Iterator<Item = (String, u64)> + '_ {
+        // 0%1:23:00:: 1 20
+        // ^^^^^^^^^^^ ^ ^^
+        //  sense key  | tag count
+        //     sense number
+        self.cntlist_rev.lines().filter_map(|line| {
+            let mut tokens = line.split(' ');
+            let sense_key = tokens.next()?;
+            let lemma = &sense_key[..sense_key.find('%')?];
+            let count = tokens.nth(1)?.parse().ok()?;
+            Some((lemma.to_string(), count))
+        })
+    }
+
+    /// Returns every synset in the Wordnet data files.
+    pub(crate) fn synsets(&self) -> impl Iterator<Item = Synset> + '_ {
+        data::synsets(
+            WORD_TYPES
+                .iter()
+                .map(|&word_type| self.data(word_type))
+                .collect(),
+        )
+    }
+
+    /// Returns every sense in `index.sense`. [`check`](Self::check) reports any that don't
+    /// parse, but here they are logged and skipped.
+    fn senses(&self) -> impl Iterator<Item = Sense> + '_ {
+        self.index_sense
+            .lines()
+            .filter_map(|line| match line.parse::<Sense>() {
+                Ok(sense) => Some(sense),
+                Err(e) => {
+                    log::warn!("Skipping Wordnet sense: {}", e);
+                    None
+                }
+            })
+    }
+
+    /// Hashes the contents of every file, for telling one copy of Wordnet from another.
+    pub(crate) fn hash_files<H: Hasher>(&self, hasher: &mut H) {
+        for file in &[
+            &self.index_sense,
+            &self.cntlist_rev,
+            &self.data_noun,
+            &self.data_verb,
+            &self.data_adj,
+            &self.data_adv,
+        ] {
+            file.hash(hasher);
         }
-        map.insert(lemma, best_definitions);
     }
 
-    map
+    /// The data file for a part of speech.
+    fn data(&self, word_type: WordType) -> &str {
+        match word_type {
+            WordType::Noun => &self.data_noun,
+            WordType::Verb => &self.data_verb,
+            WordType::Adj => &self.data_adj,
+            WordType::Adv => &self.data_adv,
+        }
+    }
+}
+
+/// Checks that every line of an `index.sense` file is a sense.
+fn check_senses(path: &Path, index_sense: &str) -> Result<(), WordnetError> {
+    for (i, line) in index_sense.lines().enumerate() {
+        if let Err(source) = line.parse::<Sense>() {
+            return Err(WordnetError::Parse {
+                path: path.to_path_buf(),
+                line: i + 1,
+                source,
+            });
+        }
+    }
+    Ok(())
 }
 
 struct SenseList {
@@ -71,3 +228,23 @@ impl SenseList {
             .find(|sense| sense.word_type == word_type)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn report_bad_senses() {
+        let path = Path::new("dict/index.sense");
+        let good = "dog%1:05:00:: 02086723 1 42\n";
+        assert!(check_senses(path, good).is_ok());
+        let bad = format!("{}dog%1:05:00:: lots 1 42\n{}", good, good);
+        match check_senses(path, &bad) {
+            Err(WordnetError::Parse { line, source, .. }) => {
+                assert_eq!(line, 2);
+                assert!(source.to_string().starts_with("Unparseable Synset Offset"));
+            }
+            other => panic!("Expected a parse error, got {:?}", other),
+        }
+    }
+}
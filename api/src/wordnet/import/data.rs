@@ -1,21 +1,8 @@
-use super::{Sense, WordType};
-use WordType::*;
+use super::{Sense, Synset};
 
-const DATA_NOUN: &str = include_str!("../data/data.noun");
-const DATA_VERB: &str = include_str!("../data/data.verb");
-const DATA_ADJ: &str = include_str!("../data/data.adj");
-const DATA_ADV: &str = include_str!("../data/data.adv");
-
-pub fn lookup(sense: &Sense) -> Option<String> {
-    // The sense gives us a word type and a file offset.  We match the word
-    // type to one of the DATA_* constants above, then read a line at the
-    // given offset.
-    let file = match sense.word_type {
-        Noun => DATA_NOUN,
-        Verb => DATA_VERB,
-        Adj => DATA_ADJ,
-        Adv => DATA_ADV,
-    };
+pub fn lookup(file: &str, sense: &Sense) -> Option<String> {
+    // The sense gives us a file offset in the data file for its word type. We read a line at
+    // that offset.
 
     // The line consists of some metadata, a `|` character, and the
     // definition terminated by \n.
@@ -23,6 +10,16 @@ pub fn lookup(sense: &Sense) -> Option<String> {
     line.find('|').map(|pos| line[pos + 1..].trim().to_string())
 }
 
-fn read_line_at(file: &'static str, offset: usize) -> Option<&'static str> {
-    file[offset..].lines().next()
+fn read_line_at(file: &str, offset: usize) -> Option<&str> {
+    file.get(offset..)?.lines().next()
+}
+
+/// Returns every synset in some Wordnet data files.
+pub fn synsets(files: Vec<&str>) -> impl Iterator<Item = Synset> + '_ {
+    files
+        .into_iter()
+        .flat_map(|file| file.lines())
+        // The license text at the top of each file is indented by two spaces.
+        .filter(|line| !line.starts_with("  "))
+        .filter_map(|line| line.parse::<Synset>().ok())
 }
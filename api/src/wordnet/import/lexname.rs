@@ -0,0 +1,68 @@
+/// The names of Wordnet's lexicographer files, indexed by file number. Every synset comes from
+/// one of these files, so the name gives a rough category for the words in it. See
+/// [lexnames(5WN)](https://wordnet.princeton.edu/documentation/lexnames5wn).
+const LEXNAMES: [&str; 45] = [
+    "adj.all",
+    "adj.pert",
+    "adv.all",
+    "noun.Tops",
+    "noun.act",
+    "noun.animal",
+    "noun.artifact",
+    "noun.attribute",
+    "noun.body",
+    "noun.cognition",
+    "noun.communication",
+    "noun.event",
+    "noun.feeling",
+    "noun.food",
+    "noun.group",
+    "noun.location",
+    "noun.motive",
+    "noun.object",
+    "noun.person",
+    "noun.phenomenon",
+    "noun.plant",
+    "noun.possession",
+    "noun.process",
+    "noun.quantity",
+    "noun.relation",
+    "noun.shape",
+    "noun.state",
+    "noun.substance",
+    "noun.time",
+    "verb.body",
+    "verb.change",
+    "verb.cognition",
+    "verb.communication",
+    "verb.competition",
+    "verb.consumption",
+    "verb.contact",
+    "verb.creation",
+    "verb.emotion",
+    "verb.motion",
+    "verb.perception",
+    "verb.possession",
+    "verb.social",
+    "verb.stative",
+    "verb.weather",
+    "adj.ppl",
+];
+
+/// Returns the name of a lexicographer file, such as "noun.animal", given its number.
+pub fn lexname(lex_filenum: u8) -> Option<&'static str> {
+    LEXNAMES.get(usize::from(lex_filenum)).copied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn lexnames_by_number() {
+        assert_eq!(lexname(5), Some("noun.animal"));
+        assert_eq!(lexname(15), Some("noun.location"));
+        assert_eq!(lexname(44), Some("adj.ppl"));
+        assert_eq!(lexname(45), None);
+    }
+}
@@ -1,14 +1,16 @@
 use std::str::FromStr;
+use thiserror::Error;
 
 use super::WordType;
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
+#[error("{message} in {line:?}")]
 pub struct ParseError {
     line: String,
     message: String,
 }
 
-fn parse_error(line: &str, message: &str) -> ParseError {
+pub(super) fn parse_error(line: &str, message: &str) -> ParseError {
     ParseError {
         line: line.to_string(),
         message: message.to_string(),
@@ -20,14 +22,20 @@ pub struct Sense {
     pub word_type: WordType,
     pub offset: u64,
     pub sense_num: u32,
+    /// The number of the lexicographer file the sense came from. See [`lexname`].
+    ///
+    /// [`lexname`]: super::lexname
+    pub lex_filenum: u8,
 }
 
 impl FromStr for Sense {
     type Err = ParseError;
 
     // dog%1:05:00:: 02086723 1 42
-    // ^^^ ^         ^^^^^^^^ ^
-    //  |  word type offset   sense_num
+    // ^^^ ^ ^^      ^^^^^^^^ ^
+    //  |  | |       offset   sense_num
+    //  |  | lex_filenum
+    //  |  word type
     // lemma
     fn from_str(line: &str) -> Result<Self, Self::Err> {
         let mut tokens = line.split(' ');
@@ -52,12 +60,18 @@ impl FromStr for Sense {
         }
         let word_type = parse_word_type(&line[pct_pos + 1..][0..1])
             .ok_or_else(|| parse_error(line, "Unknown word_type"))?;
+        let lex_filenum = sense_key[pct_pos + 1..]
+            .split(':')
+            .nth(1)
+            .and_then(|n| n.parse::<u8>().ok())
+            .ok_or_else(|| parse_error(line, "Unparseable Lexicographer File Number"))?;
 
         Ok(Self {
             lemma,
             word_type,
             offset,
             sense_num,
+            lex_filenum,
         })
     }
 }
@@ -0,0 +1,158 @@
+use std::str::FromStr;
+
+use super::sense::{parse_error, ParseError};
+use super::WordType;
+
+/// A set of synonyms read from one of Wordnet's `data.*` files.
+#[derive(Debug, PartialEq)]
+pub struct Synset {
+    pub word_type: WordType,
+    pub offset: u64,
+    pub lemmas: Vec<String>,
+    pub pointers: Vec<Pointer>,
+}
+
+/// A link from one synset to another.
+#[derive(Debug, PartialEq)]
+pub struct Pointer {
+    pub relation: Relation,
+    pub word_type: WordType,
+    pub offset: u64,
+}
+
+/// The pointer types we care about. Wordnet has many more (antonyms, meronyms, and so on),
+/// and they are all lumped together as `Other`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Relation {
+    Hypernym,
+    SimilarTo,
+    Other,
+}
+
+impl FromStr for Synset {
+    type Err = ParseError;
+
+    // 00003552 00 s 02 emergent 0 emerging 0 003 & 00003356 a 0000 + ... | gloss
+    // ^^^^^^^^    ^ ^^ ^^^^^^^^ ^            ^^^ ^^^^^^^^^^^^^^^^^^^^
+    //  offset     | |  lemma    lex_id       |   pointer
+    //       word type  word count (hex)      pointer count
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let end = line.find('|').unwrap_or(line.len());
+        let mut tokens = line[..end].split_whitespace();
+        let mut next = |what| {
+            tokens
+                .next()
+                .ok_or_else(|| parse_error(line, &format!("{} not found", what)))
+        };
+
+        let offset = next("Synset Offset")?
+            .parse::<u64>()
+            .map_err(|_| parse_error(line, "Unparseable Synset Offset"))?;
+        let _lex_filenum = next("Lexicographer File Number")?;
+        let word_type = parse_ss_type(next("Synset Type")?)
+            .ok_or_else(|| parse_error(line, "Unknown Synset Type"))?;
+        let word_count = usize::from_str_radix(next("Word Count")?, 16)
+            .map_err(|_| parse_error(line, "Unparseable Word Count"))?;
+
+        let mut lemmas = Vec::with_capacity(word_count);
+        for _ in 0..word_count {
+            lemmas.push(strip_marker(next("Word")?).to_string());
+            let _lex_id = next("Lex ID")?;
+        }
+
+        let pointer_count = next("Pointer Count")?
+            .parse::<usize>()
+            .map_err(|_| parse_error(line, "Unparseable Pointer Count"))?;
+
+        let mut pointers = Vec::with_capacity(pointer_count);
+        for _ in 0..pointer_count {
+            let relation = parse_relation(next("Pointer Symbol")?);
+            let offset = next("Pointer Offset")?
+                .parse::<u64>()
+                .map_err(|_| parse_error(line, "Unparseable Pointer Offset"))?;
+            let word_type = parse_ss_type(next("Pointer Type")?)
+                .ok_or_else(|| parse_error(line, "Unknown Pointer Type"))?;
+            let _source_target = next("Pointer Source/Target")?;
+            pointers.push(Pointer {
+                relation,
+                word_type,
+                offset,
+            });
+        }
+
+        Ok(Self {
+            word_type,
+            offset,
+            lemmas,
+            pointers,
+        })
+    }
+}
+
+/// Adjectives can carry a syntactic marker such as `(a)` or `(ip)`, which isn't part of the word.
+fn strip_marker(word: &str) -> &str {
+    match word.find('(') {
+        Some(pos) => &word[..pos],
+        None => word,
+    }
+}
+
+fn parse_ss_type(ss_type: &str) -> Option<WordType> {
+    match ss_type {
+        "n" => Some(WordType::Noun),
+        "v" => Some(WordType::Verb),
+        "a" | "s" => Some(WordType::Adj),
+        "r" => Some(WordType::Adv),
+        _ => None,
+    }
+}
+
+fn parse_relation(symbol: &str) -> Relation {
+    match symbol {
+        "@" | "@i" => Relation::Hypernym,
+        "&" => Relation::SimilarTo,
+        _ => Relation::Other,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn parse_adjective_satellite() {
+        let line = "00003552 00 s 02 emergent 0 emerging 0 003 & 00003356 a 0000 \
+                    + 02631097 v 0102 + 00051513 n 0101 | coming into existence";
+        let synset = line.parse::<Synset>().unwrap();
+        assert_eq!(synset.offset, 3552);
+        assert_eq!(synset.word_type, WordType::Adj);
+        assert_eq!(synset.lemmas, vec!["emergent", "emerging"]);
+        assert_eq!(
+            synset.pointers[0],
+            Pointer {
+                relation: Relation::SimilarTo,
+                word_type: WordType::Adj,
+                offset: 3356
+            }
+        );
+        assert_eq!(synset.pointers[1].relation, Relation::Other);
+    }
+
+    #[test]
+    pub fn parse_verb_with_frames() {
+        let line = "00002325 29 v 01 respire 1 002 $ 00001740 v 0000 @ 02112831 v 0000 \
+                    01 + 02 00 | undergo the biomedical and metabolic processes of respiration";
+        let synset = line.parse::<Synset>().unwrap();
+        assert_eq!(synset.lemmas, vec!["respire"]);
+        assert_eq!(synset.pointers.len(), 2);
+        assert_eq!(synset.pointers[1].relation, Relation::Hypernym);
+        assert_eq!(synset.pointers[1].offset, 2112831);
+    }
+
+    #[test]
+    pub fn parse_marked_adjective() {
+        let line = "00013160 00 a 01 able(a) 0 000 | having the necessary means";
+        let synset = line.parse::<Synset>().unwrap();
+        assert_eq!(synset.lemmas, vec!["able"]);
+    }
+}
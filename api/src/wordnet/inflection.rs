@@ -0,0 +1,287 @@
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::stemming::{
+    RuleSet, ADJ_EXC, ADJ_RULES, ADV_EXC, NOUN_EXC, NOUN_RULES, VERB_EXC, VERB_RULES,
+};
+use super::{StemmedWord, WordType};
+
+/// Maps a base form to its irregular inflections, e.g. "go" to "gone" and "went".
+type InflectionsTable = HashMap<&'static str, Vec<&'static str>>;
+
+/// The ways an English word can be inflected.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Inflection {
+    /// dog -> dogs
+    Plural,
+    /// run -> runs
+    ThirdPerson,
+    /// walk -> walked. Irregular past tenses and past participles are lumped together.
+    Past,
+    /// walk -> walking
+    PresentParticiple,
+    /// big -> bigger
+    Comparative,
+    /// big -> biggest
+    Superlative,
+}
+
+impl Inflection {
+    /// Works out how `inflected` was derived from its stemmed base form, or returns `None`
+    /// if it is the base form. For phrases, this goes by the head word that
+    /// [`inflect_phrase`] would inflect.
+    pub fn detect(inflected: &str, stemmed: &StemmedWord) -> Option<Self> {
+        if inflected == stemmed.base_form {
+            None
+        } else if stemmed.word_type == WordType::Verb {
+            let head = inflected.split(' ').next().unwrap_or(inflected);
+            Some(Self::classify(head, stemmed.word_type))
+        } else {
+            let head = inflected.rsplit(' ').next().unwrap_or(inflected);
+            Some(Self::classify(head, stemmed.word_type))
+        }
+    }
+
+    /// Guesses the inflection of a form that is known not to be a base form, by its ending.
+    fn classify(inflected: &str, word_type: WordType) -> Self {
+        match word_type {
+            WordType::Noun => Inflection::Plural,
+            WordType::Verb if inflected.ends_with("ing") => Inflection::PresentParticiple,
+            WordType::Verb if inflected.ends_with('s') => Inflection::ThirdPerson,
+            WordType::Verb => Inflection::Past,
+            WordType::Adj | WordType::Adv if inflected.ends_with("st") => Inflection::Superlative,
+            WordType::Adj | WordType::Adv => Inflection::Comparative,
+        }
+    }
+}
+
+/// A possible inflection of a base form.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize)]
+pub struct Inflected {
+    pub word: String,
+    pub word_type: WordType,
+    pub inflection: Inflection,
+}
+
+/// Lists the possible inflections of a base form: first its irregular forms from Wordnet's
+/// exception lists, then every form that the stemming rules would reduce back to it.
+///
+/// This is the inverse of the stemmer, so it overgenerates in the same way: "change" gives
+/// "changed" but also "changeed". Check the results against a word list to weed these out, as
+/// [`Lexicon::inflect`] does.
+///
+/// [`Lexicon::inflect`]: crate::Lexicon::inflect
+pub fn inflections(base_form: &str, word_type: WordType) -> Vec<Inflected> {
+    let (table, rules): (&InflectionsTable, RuleSet) = match word_type {
+        WordType::Noun => (&NOUN_INFLECTIONS, NOUN_RULES),
+        WordType::Verb => (&VERB_INFLECTIONS, VERB_RULES),
+        WordType::Adj => (&ADJ_INFLECTIONS, ADJ_RULES),
+        WordType::Adv => (&ADV_INFLECTIONS, &[]),
+    };
+
+    let irregular = table
+        .get(base_form)
+        .into_iter()
+        .flatten()
+        .map(|form| form.to_string());
+    let regular = rules.iter().filter_map(|(suffix, replacement)| {
+        if base_form.ends_with(replacement) {
+            Some(replace_suffix(base_form, replacement, suffix))
+        } else {
+            None
+        }
+    });
+
+    irregular
+        .chain(regular)
+        .filter(|form| form != base_form)
+        .unique()
+        .map(|word| Inflected {
+            inflection: Inflection::classify(&word, word_type),
+            word,
+            word_type,
+        })
+        .collect()
+}
+
+/// Inflects a phrase by inflecting one of its words: the first word of a verb phrase ("take a
+/// breath" -> "took a breath"), or the last word of anything else ("hot dog" -> "hot dogs").
+///
+/// The word is inflected to one of its forms from [`inflections`] that `is_word` accepts, so
+/// that overgenerated forms like "rapider" can be weeded out with a word list. Irregular forms
+/// win over regular ones, and the simple past ("went") over the participle ("gone"). Returns
+/// `None` if no form is accepted.
+pub fn inflect_phrase<F>(
+    phrase: &str,
+    word_type: WordType,
+    inflection: Inflection,
+    is_word: F,
+) -> Option<String>
+where
+    F: Fn(&str) -> bool,
+{
+    let (start, end) = if word_type == WordType::Verb {
+        (0, phrase.find(' ').unwrap_or(phrase.len()))
+    } else {
+        (phrase.rfind(' ').map_or(0, |pos| pos + 1), phrase.len())
+    };
+    let forms = inflections(&phrase[start..end], word_type)
+        .into_iter()
+        .filter(|form| form.inflection == inflection && is_word(&form.word))
+        .map(|form| form.word)
+        .collect::<Vec<_>>();
+    let form = if inflection == Inflection::Past {
+        forms
+            .iter()
+            .find(|form| !form.ends_with('n') && !form.ends_with("ne"))
+            .or_else(|| forms.first())
+    } else {
+        forms.first()
+    }?;
+    Some(format!("{}{}{}", &phrase[..start], form, &phrase[end..]))
+}
+
+fn replace_suffix(base_form: &str, suffix: &str, replacement: &str) -> String {
+    format!(
+        "{}{}",
+        &base_form[..base_form.len() - suffix.len()],
+        replacement
+    )
+}
+
+fn reverse(table: &'static HashMap<&'static str, Vec<&'static str>>) -> InflectionsTable {
+    let mut map = InflectionsTable::default();
+    for (inflected, base_forms) in table.iter() {
+        for base_form in base_forms {
+            map.entry(*base_form).or_default().push(*inflected);
+        }
+    }
+    // HashMap iteration order is arbitrary, so sort to keep results stable.
+    for forms in map.values_mut() {
+        forms.sort_unstable();
+    }
+    map
+}
+
+lazy_static! {
+    static ref NOUN_INFLECTIONS: InflectionsTable = reverse(&NOUN_EXC);
+    static ref VERB_INFLECTIONS: InflectionsTable = reverse(&VERB_EXC);
+    static ref ADJ_INFLECTIONS: InflectionsTable = reverse(&ADJ_EXC);
+    static ref ADV_INFLECTIONS: InflectionsTable = reverse(&ADV_EXC);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::wordnet::stem;
+    use Inflection::*;
+    use WordType::*;
+
+    fn inflect(phrase: &str, word_type: WordType, inflection: Inflection) -> Option<String> {
+        let words = [
+            "bigger",
+            "changed",
+            "changing",
+            "dogs",
+            "gone",
+            "hot",
+            "kinsmen",
+            "ladies",
+            "mice",
+            "quicker",
+            "ran",
+            "running",
+            "strangest",
+            "took",
+            "went",
+        ];
+        inflect_phrase(phrase, word_type, inflection, |word| words.contains(&word))
+    }
+
+    #[test]
+    pub fn inflect_words() {
+        assert_eq!(inflect("dog", Noun, Plural).unwrap(), "dogs");
+        assert_eq!(inflect("lady", Noun, Plural).unwrap(), "ladies");
+        assert_eq!(inflect("kinsman", Noun, Plural).unwrap(), "kinsmen");
+        assert_eq!(inflect("mouse", Noun, Plural).unwrap(), "mice");
+        assert_eq!(inflect("change", Verb, Past).unwrap(), "changed");
+        assert_eq!(
+            inflect("change", Verb, PresentParticiple).unwrap(),
+            "changing"
+        );
+        assert_eq!(inflect("go", Verb, Past).unwrap(), "went");
+        assert_eq!(inflect("run", Verb, Past).unwrap(), "ran");
+        assert_eq!(inflect("run", Verb, PresentParticiple).unwrap(), "running");
+        assert_eq!(inflect("big", Adj, Comparative).unwrap(), "bigger");
+        assert_eq!(inflect("strange", Adj, Superlative).unwrap(), "strangest");
+        assert_eq!(inflect("quick", Adj, Comparative).unwrap(), "quicker");
+    }
+
+    #[test]
+    pub fn inflect_only_to_words() {
+        assert_eq!(inflect("rapid", Adj, Comparative), None);
+        assert_eq!(inflect("walk", Verb, Past), None);
+        assert_eq!(inflect("run", Verb, ThirdPerson), None);
+    }
+
+    #[test]
+    pub fn inflect_phrases() {
+        assert_eq!(inflect("hot dog", Noun, Plural).unwrap(), "hot dogs");
+        assert_eq!(
+            inflect("take a breath", Verb, Past).unwrap(),
+            "took a breath"
+        );
+        assert_eq!(inflect("dog", Verb, Past), None);
+    }
+
+    fn forms(base_form: &str, word_type: WordType) -> Vec<(String, Inflection)> {
+        inflections(base_form, word_type)
+            .into_iter()
+            .map(|i| (i.word, i.inflection))
+            .collect()
+    }
+
+    #[test]
+    pub fn list_noun_inflections() {
+        assert_eq!(forms("dog", Noun), vec![("dogs".to_string(), Plural)]);
+        assert_eq!(forms("mouse", Noun)[0], ("mice".to_string(), Plural));
+        assert!(forms("church", Noun).contains(&("churches".to_string(), Plural)));
+    }
+
+    #[test]
+    pub fn list_verb_inflections() {
+        let forms = forms("run", Verb);
+        assert_eq!(forms[0], ("ran".to_string(), Past));
+        assert_eq!(forms[1], ("running".to_string(), PresentParticiple));
+        assert!(forms.contains(&("runs".to_string(), ThirdPerson)));
+        assert!(forms.contains(&("runed".to_string(), Past)));
+    }
+
+    #[test]
+    pub fn list_adjective_inflections() {
+        // The rules overgenerate, just as they do for stemming: "gooder" stems to "good".
+        assert_eq!(
+            forms("good", Adj),
+            vec![
+                ("best".to_string(), Superlative),
+                ("better".to_string(), Comparative),
+                ("gooder".to_string(), Comparative),
+                ("goodest".to_string(), Superlative),
+            ]
+        );
+        assert!(forms("late", Adj).contains(&("later".to_string(), Comparative)));
+        assert!(forms("late", Adj).contains(&("latest".to_string(), Superlative)));
+    }
+
+    #[test]
+    pub fn detect_inflection() {
+        let detect = |word| stem(word).next().and_then(|s| Inflection::detect(word, &s));
+        assert_eq!(detect("dogs"), Some(Plural));
+        assert_eq!(detect("running"), Some(PresentParticiple));
+        assert_eq!(detect("went"), Some(Past));
+        assert_eq!(detect("worse"), Some(Comparative));
+    }
+}
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use std::iter;
 
 use super::WordType;
+use crate::phrase_key;
 
 type ExclusionsTable = HashMap<&'static str, Vec<&'static str>>;
 
@@ -32,9 +33,41 @@ pub fn stem(s: &str) -> Box<dyn Iterator<Item = StemmedWord> + '_> {
     }
 }
 
-type RuleSet = &'static [(&'static str, &'static str)];
+/// Stems a phrase key (see [`phrase_key`]) by stemming its head word: the first word of a verb
+/// phrase ("running away" -> "run away"), or the last word of anything else ("alarm clocks" ->
+/// "alarm clock"). Phrases in the exception tables, such as "brothers-in-law", are stemmed as a
+/// whole. Single words are stemmed as usual.
+///
+/// [`phrase_key`]: crate::phrase_key
+pub fn stem_phrase(phrase: &str) -> Vec<StemmedWord> {
+    let (first, last) = match (phrase.find(' '), phrase.rfind(' ')) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return stem(phrase).collect(),
+    };
 
-static NOUN_RULES: RuleSet = &[
+    // The exception tables join the words of a phrase with underscores or hyphens.
+    let whole = ["_", "-"].iter().flat_map(|sep| {
+        find_all_exceptions(&phrase.replace(' ', sep))
+            .map(|stemmed| StemmedWord::new(phrase_key(&stemmed.base_form), stemmed.word_type))
+    });
+    let verbs = stem(&phrase[..first])
+        .filter(|stemmed| stemmed.word_type == WordType::Verb)
+        .map(|stemmed| {
+            let base_form = format!("{}{}", stemmed.base_form, &phrase[first..]);
+            StemmedWord::new(base_form, WordType::Verb)
+        });
+    let others = stem(&phrase[last + 1..])
+        .filter(|stemmed| stemmed.word_type != WordType::Verb)
+        .map(|stemmed| {
+            let base_form = format!("{}{}", &phrase[..=last], stemmed.base_form);
+            StemmedWord::new(base_form, stemmed.word_type)
+        });
+    whole.chain(verbs).chain(others).unique().collect()
+}
+
+pub(super) type RuleSet = &'static [(&'static str, &'static str)];
+
+pub(super) static NOUN_RULES: RuleSet = &[
     ("s", ""),
     ("ses", "s"),
     ("xes", "x"),
@@ -45,7 +78,7 @@ static NOUN_RULES: RuleSet = &[
     ("ies", "y"),
 ];
 
-static VERB_RULES: RuleSet = &[
+pub(super) static VERB_RULES: RuleSet = &[
     ("s", ""),
     ("ies", "y"),
     ("es", "e"),
@@ -56,7 +89,7 @@ static VERB_RULES: RuleSet = &[
     ("ing", ""),
 ];
 
-static ADJ_RULES: RuleSet = &[("er", ""), ("est", ""), ("er", "e"), ("est", "e")];
+pub(super) static ADJ_RULES: RuleSet = &[("er", ""), ("est", ""), ("er", "e"), ("est", "e")];
 
 fn stem_by_rules(input: &str) -> impl Iterator<Item = StemmedWord> + '_ {
     stem_ruleset(&NOUN_RULES, WordType::Noun, input)
@@ -214,4 +247,26 @@ mod test {
             stem("strangest").collect::<Vec<_>>()
         );
     }
+
+    #[test]
+    pub fn stem_phrases() {
+        let base_forms = |phrase| {
+            stem_phrase(phrase)
+                .into_iter()
+                .map(|stemmed| (stemmed.base_form, stemmed.word_type))
+                .collect::<Vec<_>>()
+        };
+        assert!(base_forms("alarm clocks").contains(&("alarm clock".to_string(), WordType::Noun)));
+        assert!(base_forms("running away").contains(&("run away".to_string(), WordType::Verb)));
+        assert!(
+            base_forms("took a breath").contains(&("take a breath".to_string(), WordType::Verb))
+        );
+        assert!(!base_forms("alarm clocks").contains(&("alarm clocks".to_string(), WordType::Verb)));
+        assert_eq!(
+            base_forms("dogs"),
+            stem("dogs")
+                .map(|s| (s.base_form, s.word_type))
+                .collect::<Vec<_>>()
+        );
+    }
 }
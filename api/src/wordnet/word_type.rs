@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use thiserror::Error;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize)]
 pub enum WordType {
@@ -7,3 +9,36 @@ pub enum WordType {
     Adj,
     Adv,
 }
+
+#[derive(Debug, Error)]
+#[error("unknown part of speech {0:?}")]
+pub struct UnknownWordType(String);
+
+impl FromStr for WordType {
+    type Err = UnknownWordType;
+
+    /// Parses a part of speech, either spelled out ("adjective"), abbreviated ("adj"), or as
+    /// Wordnet's one-letter code ("a").
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "noun" | "n" => Ok(WordType::Noun),
+            "verb" | "v" => Ok(WordType::Verb),
+            "adjective" | "adj" | "a" | "s" => Ok(WordType::Adj),
+            "adverb" | "adv" | "r" => Ok(WordType::Adv),
+            _ => Err(UnknownWordType(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn parse_word_type() {
+        assert_eq!("noun".parse::<WordType>().unwrap(), WordType::Noun);
+        assert_eq!("Adj".parse::<WordType>().unwrap(), WordType::Adj);
+        assert_eq!("r".parse::<WordType>().unwrap(), WordType::Adv);
+        assert!("pronoun".parse::<WordType>().is_err());
+    }
+}
@@ -11,7 +11,8 @@ async function fetchPreview(
     return null;
   } else {
     const response = await fetch(`${path}?q=${encodeURIComponent(input)}`);
-    const result = await response.json();
+    // The server responds with a 400 if it can't parse the query; show no preview for those.
+    const result = response.ok ? await response.json() : null;
     if (inflight.current === input) {
       return result;
     } else {
@@ -55,9 +55,9 @@ function Wordfun() {
     const query = { type, q };
     inflight.current = query;
     const response = await fetch(`/words/${type}?q=${encodeURIComponent(q)}`);
-    const results: { words: DictEntry[] } = await response.json();
+    const results: { words?: DictEntry[] } = await response.json();
     if (inflight.current && inflight.current === query) {
-      const words = results.words;
+      const words = results.words || [];
       setResult({ q, words });
     }
   }